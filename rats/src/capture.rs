@@ -0,0 +1,125 @@
+use crate::{errors::Result, high_scores::now, maze::MazeFile};
+use std::{
+    path::{Path, PathBuf},
+    time::Instant,
+};
+use video::{Buffer, GifRecorder, SoftwareRenderer};
+
+// time between GIF frames (there's no point in recording every frame)
+const GIF_FRAME_MS: u32 = 50;
+
+// Screenshots and GIF clips are drawn from the video buffer with the software
+// renderer so they look exactly like the screen. Files are saved in the
+// capture directory as rats-<seconds since 1970>.png or .gif (or .maze for
+// the current maze).
+//
+// GIFs are recorded at scale 1 to keep them small, recording stops by itself
+// if the window changes size.
+pub struct Capture {
+    dir: PathBuf,
+    screenshots: SoftwareRenderer,
+    clips: SoftwareRenderer,
+    recording: Option<Recording>,
+}
+
+struct Recording {
+    gif: GifRecorder,
+    path: PathBuf,
+    last_frame: Instant,
+}
+
+impl Capture {
+    pub fn new(dir: &Path, scale: usize, color: bool) -> Capture {
+        Capture {
+            dir: dir.to_path_buf(),
+            screenshots: SoftwareRenderer::new(scale, color),
+            clips: SoftwareRenderer::new(1, color),
+            recording: None,
+        }
+    }
+
+    // the scale changed along with the window size
+    pub fn set_scale(&mut self, scale: usize, color: bool) {
+        self.screenshots = SoftwareRenderer::new(scale, color);
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn screenshot(&self, buffer: &Buffer) -> Result<PathBuf> {
+        let path = self.new_path("png");
+        self.screenshots.render(buffer).save_png(&path)?;
+        Ok(path)
+    }
+
+    pub fn save_maze(&self, maze_file: &MazeFile) -> Result<PathBuf> {
+        let path = self.new_path("maze");
+        maze_file.save(&path)?;
+        Ok(path)
+    }
+
+    // start recording a GIF or finish the one being recorded, returns the
+    // name of the GIF
+    pub fn toggle_recording(&mut self, buffer: &Buffer) -> Result<PathBuf> {
+        match self.recording.take() {
+            Some(recording) => {
+                recording.gif.finish()?;
+                Ok(recording.path)
+            }
+            None => {
+                let path = self.new_path("gif");
+                let image = self.clips.render(buffer);
+                let mut gif = GifRecorder::create(
+                    &path,
+                    image.width,
+                    image.height,
+                    GIF_FRAME_MS,
+                )?;
+                gif.add_frame(&image)?;
+                self.recording = Some(Recording {
+                    gif,
+                    path: path.clone(),
+                    last_frame: Instant::now(),
+                });
+                Ok(path)
+            }
+        }
+    }
+
+    // called with every frame, only some of them end up in the GIF, returns
+    // the name of the GIF if recording had to stop (errors stop it too)
+    pub fn frame(&mut self, buffer: &Buffer) -> Result<Option<PathBuf>> {
+        let Some(recording) = &mut self.recording else {
+            return Ok(None);
+        };
+        if recording.last_frame.elapsed().as_millis() < GIF_FRAME_MS as u128 {
+            return Ok(None);
+        }
+        recording.last_frame = Instant::now();
+        let image = self.clips.render(buffer);
+        if image.width != recording.gif.width()
+            || image.height != recording.gif.height()
+        {
+            return self.toggle_recording(buffer).map(Some);
+        }
+        if let Err(error) = recording.gif.add_frame(&image) {
+            self.recording = None;
+            return Err(error.into());
+        }
+        Ok(None)
+    }
+
+    // rats-<seconds>.<extension>, or rats-<seconds>-<n>.<extension> if there
+    // is already more than one in the same second
+    fn new_path(&self, extension: &str) -> PathBuf {
+        let seconds = now();
+        let mut path = self.dir.join(format!("rats-{seconds}.{extension}"));
+        let mut n = 2;
+        while path.exists() {
+            path = self.dir.join(format!("rats-{seconds}-{n}.{extension}"));
+            n += 1;
+        }
+        path
+    }
+}
@@ -0,0 +1,84 @@
+use std::time::{Duration, Instant};
+
+// The simulation never looks at the wall clock directly. All entity timing is
+// measured in milliseconds of game time, which only moves forward when the
+// game loop advances the clock.
+pub trait Clock {
+    // game time in milliseconds
+    fn now(&self) -> u32;
+
+    // move the clock forward, time doesn't flow unless the game is running
+    fn advance(&mut self, running: bool);
+
+    // start over at zero
+    fn reset(&mut self);
+}
+
+// game time follows the wall clock
+pub struct RealClock {
+    last: Instant,
+    elapsed: Duration,
+}
+
+impl RealClock {
+    pub fn new() -> RealClock {
+        RealClock {
+            last: Instant::now(),
+            elapsed: Duration::ZERO,
+        }
+    }
+}
+
+impl Default for RealClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for RealClock {
+    fn now(&self) -> u32 {
+        self.elapsed.as_millis() as u32
+    }
+
+    fn advance(&mut self, running: bool) {
+        let now = Instant::now();
+        if running {
+            self.elapsed += now - self.last;
+        }
+        self.last = now;
+    }
+
+    fn reset(&mut self) {
+        self.last = Instant::now();
+        self.elapsed = Duration::ZERO;
+    }
+}
+
+// game time moves forward by a fixed step every time the clock is advanced,
+// no matter how much (or how little) real time has passed
+pub struct SimulatedClock {
+    step: u32,
+    now: u32,
+}
+
+impl SimulatedClock {
+    pub fn new(step: u32) -> SimulatedClock {
+        SimulatedClock { step, now: 0 }
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> u32 {
+        self.now
+    }
+
+    fn advance(&mut self, running: bool) {
+        if running {
+            self.now += self.step;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.now = 0;
+    }
+}
@@ -1,34 +1,152 @@
-// milliseconds between updates
-pub const PLAYER_UPDATE_MS: u32 = 50;
-pub const RAT_UPDATE_MS: u32 = 100;
-pub const BRAT_UPDATE_MS: u32 = 75;
-pub const FACTORY_UPDATE_MS: u32 = 250;
-pub const BULLET_UPDATE_MS: u32 = 10;
-
-// player fire rate in nanoseconds
-pub const PLAYER_FIRE_RATE_NS: u32 = 1_000_000_000 / 8;
-
-// scoring
-pub const RAT_KILL: usize = 50;
-pub const BRAT_KILL: usize = 25;
-pub const FACTORY_KILL: usize = 250;
-
-// spawn rate
-pub const RAT_SPAWN_SECONDS: u64 = 20;
-pub const BRAT_SPAWN_SECONDS: u64 = 45;
-pub const RATS_PER_FACTORY: f32 = 2.5;
-
-// length of super boom in frames
-pub const SUPER_BOOM_FRAMES: usize = 60;
-
-// how long bullet is harmless to player after being fired (in update cycles)
+use crate::errors::{config_error, Result};
+use serde::{Deserialize, Serialize};
+use std::{cell::Cell, env, fs, path::Path, path::PathBuf};
+use video::Size;
+
+// Game tuning settings. The defaults are the values the game was designed
+// with, any of them can be changed from a TOML file:
+//
+//   rat_kill = 100
+//   rats_per_factory = 4.0
 //
-// This is done to prevent the situation where the player happens to be moving
-// in the same direction they are firing and the update cycles overlap in such a
-// way that the player briefly occupies the same position as the bullet; which
-// causes the game to treat it as a player kill. The bullet will still kill the
-// player if the maze is sparse enough so that the bullet can wrap around and
-// hit the player from the opposite direction.
-pub const BULLET_HARMLESS_LIFETIME: u32 = 10;
-
-pub const PLAYER_BLAST_RADIUS_SQUARED: u16 = 25 * 25;
+// or from the command line with --set rat_kill=100
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // milliseconds between updates
+    pub player_update_ms: u32,
+    pub rat_update_ms: u32,
+    pub brat_update_ms: u32,
+    pub factory_update_ms: u32,
+    pub bullet_update_ms: u32,
+
+    // player fire rate in milliseconds
+    pub player_fire_rate_ms: u32,
+
+    // scoring
+    pub rat_kill: usize,
+    pub brat_kill: usize,
+    pub factory_kill: usize,
+
+    // spawn rate
+    pub rat_spawn_seconds: u32,
+    pub brat_spawn_seconds: u32,
+    pub rats_per_factory: f32,
+
+    // length of super boom in frames
+    pub super_boom_frames: usize,
+
+    // how long bullet is harmless to player after being fired (in update
+    // cycles)
+    //
+    // This is done to prevent the situation where the player happens to be
+    // moving in the same direction they are firing and the update cycles
+    // overlap in such a way that the player briefly occupies the same position
+    // as the bullet; which causes the game to treat it as a player kill. The
+    // bullet will still kill the player if the maze is sparse enough so that
+    // the bullet can wrap around and hit the player from the opposite
+    // direction.
+    pub bullet_harmless_lifetime: u32,
+
+    pub player_blast_radius: Size,
+
+    // percentage of moves where rats and brats follow the shortest path
+    // through the maze to the player instead of wandering around, and how
+    // close (in steps) the player has to be for them to find it
+    pub rat_smarts: u32,
+    pub brat_smarts: u32,
+    pub hunt_range: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            player_update_ms: 50,
+            rat_update_ms: 100,
+            brat_update_ms: 75,
+            factory_update_ms: 250,
+            bullet_update_ms: 10,
+            player_fire_rate_ms: 1000 / 8,
+            rat_kill: 50,
+            brat_kill: 25,
+            factory_kill: 250,
+            rat_spawn_seconds: 20,
+            brat_spawn_seconds: 45,
+            rats_per_factory: 2.5,
+            super_boom_frames: 60,
+            bullet_harmless_lifetime: 10,
+            player_blast_radius: 25,
+            rat_smarts: 50,
+            brat_smarts: 25,
+            hunt_range: 60,
+        }
+    }
+}
+
+impl Config {
+    // settings missing from the file keep their default values
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Config> {
+        let path = path.as_ref();
+        toml::from_str(&fs::read_to_string(path)?).map_err(|error| {
+            config_error(format!("{}: {}", path.display(), error.message()))
+        })
+    }
+
+    // change a single setting, the value is written the way it would be in a
+    // config file (e.g. 100 or 2.5)
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let mut table = toml::Table::try_from(*self)
+            .map_err(|error| config_error(error.to_string()))?;
+        if !table.contains_key(key) {
+            return Err(config_error(format!("unknown setting: {key}")));
+        }
+        let invalid =
+            || config_error(format!("invalid value for {key}: {value}"));
+        let setting: toml::Table =
+            format!("{key} = {value}").parse().map_err(|_| invalid())?;
+        table.extend(setting);
+        *self = toml::Value::Table(table)
+            .try_into()
+            .map_err(|_| invalid())?;
+        Ok(())
+    }
+
+    // every setting as a (key, value) pair, in the same format that set()
+    // accepts
+    pub fn settings(&self) -> Vec<(String, String)> {
+        toml::Table::try_from(*self)
+            .map(|table| {
+                table
+                    .into_iter()
+                    .map(|(key, value)| (key, value.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn player_blast_radius_squared(&self) -> u32 {
+        let radius = self.player_blast_radius as u32;
+        radius * radius
+    }
+}
+
+thread_local! {
+    static CONFIG: Cell<Config> = Cell::new(Config::default());
+}
+
+// settings for the current game
+pub fn config() -> Config {
+    CONFIG.with(|config| config.get())
+}
+
+pub fn set_config(config: Config) {
+    CONFIG.with(|current| current.set(config));
+}
+
+// ~/.rats.toml (or the current directory if there is no home)
+pub fn default_config_path() -> PathBuf {
+    env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(".rats.toml")
+}
@@ -0,0 +1,222 @@
+use crate::{
+    errors::Result,
+    game_context::{factory_position, start_position},
+    levels::MAX_MAZE_CELLS,
+    maze::{Carve, Maze, MazeFile, MAZE_CELL_COLS, MAZE_CELL_ROWS},
+};
+use std::path::{Path, PathBuf};
+use video::{
+    Buffer, Keycode, Pos, Size, SizeWrapping, Viewport, ATTR_NONE,
+    ATTR_REVERSE, FACTORY_A1, PLAYER_DOWN,
+};
+
+// The maze editor shows the maze around the maze cell under the cursor. The
+// arrow keys move the cursor, T, B, L and R add or remove the wall on the top,
+// bottom, left or right side of the cell, F puts a rat factory in the cell
+// (or takes it away) and 1 or 2 makes it the cell where that player starts,
+// but not both in the same cell.
+// S saves the maze file and Esc leaves the editor (twice if the maze hasn't
+// been saved). These keys are fixed, the key map only changes the key that
+// switches to fullscreen.
+pub struct Editor {
+    path: PathBuf,
+    maze_file: MazeFile,
+    // the walls of the maze file, redrawn after every change
+    maze: Maze,
+    cursor: (Pos, Pos),
+    modified: bool,
+    // Esc was pressed with unsaved changes, pressing it again quits
+    confirm_quit: bool,
+    message: String,
+}
+
+impl Editor {
+    // edit an existing maze file or start a new one with every maze cell
+    // walled in
+    pub fn new<P: AsRef<Path>>(
+        path: P,
+        rows: Size,
+        cols: Size,
+    ) -> Result<Editor> {
+        let path = path.as_ref();
+        let (maze_file, message) = if path.exists() {
+            (MazeFile::load(path)?, format!("editing {}", path.display()))
+        } else {
+            let rows = rows.clamp(2, MAX_MAZE_CELLS);
+            let cols = cols.clamp(2, MAX_MAZE_CELLS);
+            (MazeFile::new(rows, cols), format!("new {}", path.display()))
+        };
+        let mut maze = Maze::new(maze_file.rows(), maze_file.cols(), false);
+        maze.draw(&maze_file.grid);
+        Ok(Editor {
+            path: path.to_path_buf(),
+            maze_file,
+            maze,
+            cursor: (0, 0),
+            modified: false,
+            confirm_quit: false,
+            message,
+        })
+    }
+
+    // returns true when it's time to leave the editor
+    pub fn key_down(&mut self, keycode: Keycode) -> bool {
+        let (row, col) = self.cursor;
+        let rows = self.maze_file.rows();
+        let cols = self.maze_file.cols();
+        let confirm_quit = std::mem::take(&mut self.confirm_quit);
+        self.message.clear();
+        match keycode {
+            Keycode::Up => self.cursor.0 = row.dec(rows),
+            Keycode::Down => self.cursor.0 = row.inc(rows),
+            Keycode::Left => self.cursor.1 = col.dec(cols),
+            Keycode::Right => self.cursor.1 = col.inc(cols),
+            Keycode::T => self.toggle_wall(Carve::Up),
+            Keycode::B => self.toggle_wall(Carve::Down),
+            Keycode::L => self.toggle_wall(Carve::Left),
+            Keycode::R => self.toggle_wall(Carve::Right),
+            Keycode::F => self.toggle_factory(),
+            Keycode::Num1 | Keycode::Kp1 => self.toggle_start(0),
+            Keycode::Num2 | Keycode::Kp2 => self.toggle_start(1),
+            Keycode::S => match self.maze_file.save(&self.path) {
+                Ok(()) => {
+                    self.modified = false;
+                    self.message = format!("saved {}", self.path.display());
+                }
+                Err(error) => self.message = error.to_string(),
+            },
+            Keycode::Escape if !self.modified || confirm_quit => return true,
+            Keycode::Escape => {
+                self.confirm_quit = true;
+                self.message = UNSAVED.to_string();
+            }
+            _ => {}
+        }
+        false
+    }
+
+    // the wall on one side of the cell under the cursor, which also changes
+    // the joiners at both ends of it
+    fn toggle_wall(&mut self, side: Carve) {
+        let (row, col) = self.cursor;
+        self.maze_file.grid.toggle_wall(row, col, side);
+        self.maze.draw(&self.maze_file.grid);
+        self.modified = true;
+    }
+
+    // a player can't start in the same cell as a factory (the maze file
+    // wouldn't load)
+    fn toggle_factory(&mut self) {
+        let factories = &mut self.maze_file.factories;
+        match factories.iter().position(|cell| *cell == self.cursor) {
+            Some(index) => {
+                factories.remove(index);
+            }
+            None => {
+                let starts = &self.maze_file.starts;
+                if let Some(player) =
+                    starts.iter().position(|start| *start == Some(self.cursor))
+                {
+                    self.message = format!("player {} starts here", player + 1);
+                    return;
+                }
+                factories.push(self.cursor);
+            }
+        }
+        self.modified = true;
+    }
+
+    // players without a starting cell start where they normally do
+    fn toggle_start(&mut self, player: usize) {
+        if self.maze_file.factories.contains(&self.cursor) {
+            self.message = "a player can't start on a factory".to_string();
+            return;
+        }
+        let start = &mut self.maze_file.starts[player];
+        *start = if *start == Some(self.cursor) {
+            None
+        } else {
+            Some(self.cursor)
+        };
+        self.modified = true;
+    }
+
+    pub fn render(&self, vbuf: &mut Buffer) {
+        vbuf.clear();
+        let mut buffer = self.maze.buffer.clone();
+        for &(row, col) in self.maze_file.factories.iter() {
+            let pos = factory_position(row, col);
+            buffer.set_quad(pos.row, pos.col, FACTORY_A1, ATTR_NONE);
+        }
+        for (player, start) in self.maze_file.starts.iter().enumerate() {
+            if let Some((row, col)) = *start {
+                let pos = start_position(player, row, col);
+                buffer.set_quad(pos.row, pos.col, PLAYER_DOWN, ATTR_NONE);
+                buffer.print(
+                    pos.row + 2,
+                    pos.col,
+                    ATTR_NONE,
+                    format!("P{}", player + 1),
+                );
+            }
+        }
+
+        // the cursor is drawn as a reversed outline around the cell
+        let (top, left) = Maze::cell_position(self.cursor.0, self.cursor.1);
+        let bottom = (top + MAZE_CELL_ROWS + 1) % buffer.rows;
+        let right = (left + MAZE_CELL_COLS + 1) % buffer.cols;
+        for col in 0..=MAZE_CELL_COLS + 1 {
+            let col = (left + col) % buffer.cols;
+            buffer.set_attr(top, col, ATTR_REVERSE);
+            buffer.set_attr(bottom, col, ATTR_REVERSE);
+        }
+        for row in 0..=MAZE_CELL_ROWS + 1 {
+            let row = (top + row) % buffer.rows;
+            buffer.set_attr(row, left, ATTR_REVERSE);
+            buffer.set_attr(row, right, ATTR_REVERSE);
+        }
+
+        // keep the cursor in the middle of the screen
+        let view = Viewport::new(2, 0, vbuf.rows.saturating_sub(2), vbuf.cols);
+        let center_row = top + 1 + MAZE_CELL_ROWS / 2;
+        let center_col = left + 1 + MAZE_CELL_COLS / 2;
+        let start_row = (center_row + buffer.rows
+            - (view.rows / 2) % buffer.rows)
+            % buffer.rows;
+        let start_col = (center_col + buffer.cols
+            - (view.cols / 2) % buffer.cols)
+            % buffer.cols;
+        buffer.copy_viewport(start_row, start_col, vbuf, view, None);
+
+        let width = vbuf.cols as usize;
+        vbuf.print(
+            0,
+            0,
+            ATTR_REVERSE,
+            format!(
+                "{:<width$}",
+                format!(
+                    " MAZE EDITOR  {}x{}  cell {},{}{}  {}",
+                    self.maze_file.rows(),
+                    self.maze_file.cols(),
+                    self.cursor.0 + 1,
+                    self.cursor.1 + 1,
+                    if self.modified { "  (modified)" } else { "" },
+                    self.message
+                )
+            ),
+        );
+        vbuf.print(
+            1,
+            0,
+            ATTR_REVERSE,
+            format!(
+                "{:<width$}",
+                " Arrows: move  T/B/L/R: wall  F: factory  \
+                1/2: player start  S: save  Esc: quit"
+            ),
+        );
+    }
+}
+
+const UNSAVED: &str = "not saved, press Esc again to quit";
@@ -3,12 +3,13 @@ use super::{
     State,
 };
 use crate::{
-    config::BRAT_UPDATE_MS,
-    game_context::{random, random_direction, Action},
+    config::config,
+    game_context::{random, random_direction, Action, GameRng},
     maze::{with_pristine_maze, Maze},
+    pathfinding::FlowField,
 };
 use video::{
-    Size, SizeWrapping, ATTR_NONE, BRATS_DOWN_A1, BRATS_DOWN_A2, BRATS_LEFT_A1,
+    Size, ATTR_NONE, BRATS_DOWN_A1, BRATS_DOWN_A2, BRATS_LEFT_A1,
     BRATS_LEFT_A2, BRATS_RIGHT_A1, BRATS_RIGHT_A2, BRATS_UP_A1, BRATS_UP_A2,
     LIL_BOOM_A1, LIL_BOOM_A2,
 };
@@ -72,71 +73,68 @@ pub fn render_brat(brat: &Brat, maze: &mut Maze) {
 pub fn update_brat(
     brat: &Brat,
     player: &Player,
-    damage: usize,
+    // (player, damage) if touching a player
+    attack: Option<(usize, usize)>,
     update: u32,
+    field: &FlowField,
+    rng: &mut GameRng,
 ) -> Action {
-    if update < brat.update + BRAT_UPDATE_MS {
+    let config = config();
+    let update_ms = config.brat_update_ms;
+    if update < brat.update + update_ms {
         return Action::Nothing;
     }
     let mut brat = *brat;
     match brat.state {
         State::Alive => {
-            if hit_player_1(brat.pos, player) {
-                return Action::Attack(damage);
+            if let Some((player, damage)) = attack {
+                return Action::Attack(player, damage);
             }
-            if let Some(dir) = player_dir(brat.pos, player.pos) {
+            // brats are smaller than rats so they can always follow the
+            // flow field, but they can also end up in spots it doesn't cover
+            let smart = config.brat_smarts > 0
+                && random(rng, 1, 100) <= config.brat_smarts;
+            let hunt = if smart {
+                field.direction(brat.pos)
+            } else {
+                None
+            };
+            if let Some(dir) = hunt {
                 brat.dir = dir;
-            }
-            if brat.distance == 0 || !brat.can_advance(brat.dir) {
-                brat.dir = random_direction();
-                brat.distance = random(5, 15);
+                brat.advance(dir);
             } else {
-                brat.advance(brat.dir);
-                brat.distance -= 1;
+                if let Some(dir) = player_dir(brat.pos, player.pos) {
+                    brat.dir = dir;
+                }
+                if brat.distance == 0 || !brat.can_advance(brat.dir) {
+                    brat.dir = random_direction(rng);
+                    brat.distance = random(rng, 5, 15);
+                } else {
+                    brat.advance(brat.dir);
+                    brat.distance -= 1;
+                }
             }
             Action::Update(Entity::Brat(Brat {
-                update: update + BRAT_UPDATE_MS,
+                update: update + update_ms,
                 cycle: (brat.cycle + 1) & 0x3,
                 ..brat
             }))
         }
         State::Exploding1 => Action::Update(Entity::Brat(Brat {
-            update: update + BRAT_UPDATE_MS / 2,
+            update: update + update_ms / 2,
             state: State::Exploding2,
             ..brat
         })),
         State::Exploding2 => Action::Update(Entity::Brat(Brat {
-            update: update + BRAT_UPDATE_MS / 2,
+            update: update + update_ms / 2,
             state: State::Exploding3,
             ..brat
         })),
         State::Exploding3 => Action::Update(Entity::Brat(Brat {
-            update: update + BRAT_UPDATE_MS / 2,
+            update: update + update_ms / 2,
             state: State::Dead,
             ..brat
         })),
         State::Dead => Action::Delete,
     }
 }
-
-pub fn hit_player_1(pos: Position, player: &Player) -> bool {
-    if pos == player.pos {
-        return true;
-    }
-    let (rows, cols) = with_pristine_maze(|maze| (maze.rows(), maze.cols()));
-    let row_1 = player.pos.row.inc(rows);
-    let col_1 = player.pos.col.inc(cols);
-    pos == Position {
-        row: player.pos.row,
-        col: col_1,
-    } || pos
-        == Position {
-            row: row_1,
-            col: player.pos.col,
-        }
-        || pos
-            == Position {
-                row: row_1,
-                col: col_1,
-            }
-}
@@ -1,6 +1,6 @@
 use super::{dir, Direction, Entity, EntityAction, Position, State};
 use crate::{
-    config::BULLET_UPDATE_MS,
+    config::config,
     game_context::Action,
     maze::{with_pristine_maze, Maze},
 };
@@ -17,6 +17,8 @@ pub struct Bullet {
     pub pos: Position,
     pub dir: Direction,
     pub state: State,
+    // the player who fired it
+    pub player: usize,
 }
 
 impl EntityAction for Bullet {
@@ -52,7 +54,8 @@ pub fn render_bullet(bullet: &Bullet, maze: &mut Maze) {
 }
 
 pub fn update_bullet(bullet: &Bullet, update: u32) -> Action {
-    if update < bullet.update + BULLET_UPDATE_MS {
+    let update_ms = config().bullet_update_ms;
+    if update < bullet.update + update_ms {
         return Action::Nothing;
     }
     with_pristine_maze(|maze| {
@@ -76,7 +79,7 @@ pub fn update_bullet(bullet: &Bullet, update: u32) -> Action {
                     Action::Delete
                 } else {
                     Action::Update(Entity::Bullet(Bullet {
-                        update: update + BULLET_UPDATE_MS,
+                        update: update + update_ms,
                         lifetime: bullet.lifetime + 1,
                         pos: Position { row, col },
                         ..bullet
@@ -84,17 +87,17 @@ pub fn update_bullet(bullet: &Bullet, update: u32) -> Action {
                 }
             }
             State::Exploding1 => Action::Update(Entity::Bullet(Bullet {
-                update: update + BULLET_UPDATE_MS,
+                update: update + update_ms,
                 state: State::Exploding2,
                 ..bullet
             })),
             State::Exploding2 => Action::Update(Entity::Bullet(Bullet {
-                update: update + BULLET_UPDATE_MS,
+                update: update + update_ms,
                 state: State::Exploding3,
                 ..bullet
             })),
             State::Exploding3 => Action::Update(Entity::Bullet(Bullet {
-                update: update + BULLET_UPDATE_MS,
+                update: update + update_ms,
                 state: State::Dead,
                 ..bullet
             })),
@@ -1,7 +1,7 @@
 use super::{dir, Entity, EntityAction, Position, Rat, State};
 use crate::{
-    config::FACTORY_UPDATE_MS,
-    game_context::{random, Action},
+    config::config,
+    game_context::{random, Action, GameRng},
     maze::{with_pristine_maze, Maze},
 };
 use video::{
@@ -71,8 +71,10 @@ pub fn update_factory(
     factory: &Factory,
     update: u32,
     make_rat: bool,
+    rng: &mut GameRng,
 ) -> Action {
-    if update < factory.update + FACTORY_UPDATE_MS {
+    let update_ms = config().factory_update_ms;
+    if update < factory.update + update_ms {
         return Action::Nothing;
     }
     let factory = *factory;
@@ -81,7 +83,7 @@ pub fn update_factory(
             if make_rat {
                 Action::New(Entity::Rat(Rat {
                     update,
-                    distance: random(5, 15),
+                    distance: random(rng, 5, 15),
                     pos: factory.pos,
                     dir: dir::RIGHT,
                     state: State::Alive,
@@ -89,24 +91,24 @@ pub fn update_factory(
                 }))
             } else {
                 Action::Update(Entity::Factory(Factory {
-                    update: update + FACTORY_UPDATE_MS,
+                    update: update + update_ms,
                     cycle: (factory.cycle + 1) & 0x1,
                     ..factory
                 }))
             }
         }
         State::Exploding1 => Action::Update(Entity::Factory(Factory {
-            update: update + FACTORY_UPDATE_MS / 2,
+            update: update + update_ms / 2,
             state: State::Exploding2,
             ..factory
         })),
         State::Exploding2 => Action::Update(Entity::Factory(Factory {
-            update: update + FACTORY_UPDATE_MS / 2,
+            update: update + update_ms / 2,
             state: State::Exploding3,
             ..factory
         })),
         State::Exploding3 => Action::Update(Entity::Factory(Factory {
-            update: update + FACTORY_UPDATE_MS / 2,
+            update: update + update_ms / 2,
             state: State::Dead,
             ..factory
         })),
@@ -0,0 +1,125 @@
+use super::Entity;
+use std::ops::{Index, IndexMut};
+
+// Entities are referred to by id rather than by their position in a list.
+// Slots are reused once an entity is removed, but the generation of a slot
+// changes every time that happens, so an id that outlives its entity won't
+// find whatever took its place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntityId {
+    slot: u32,
+    generation: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Slot {
+    generation: u32,
+    entity: Option<Entity>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EntityList {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    len: usize,
+}
+
+impl EntityList {
+    pub fn new() -> EntityList {
+        EntityList::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn insert(&mut self, entity: Entity) -> EntityId {
+        self.len += 1;
+        match self.free.pop() {
+            Some(slot) => {
+                let entry = &mut self.slots[slot as usize];
+                entry.entity = Some(entity);
+                EntityId {
+                    slot,
+                    generation: entry.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    entity: Some(entity),
+                });
+                EntityId {
+                    slot: (self.slots.len() - 1) as u32,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    pub fn remove(&mut self, id: EntityId) -> Option<Entity> {
+        self.get(id)?;
+        let entry = &mut self.slots[id.slot as usize];
+        entry.generation = entry.generation.wrapping_add(1);
+        self.free.push(id.slot);
+        self.len -= 1;
+        entry.entity.take()
+    }
+
+    // remove every entity that doesn't pass the test
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(EntityId, &Entity) -> bool,
+    {
+        let ids: Vec<EntityId> = self
+            .iter()
+            .filter(|(id, entity)| !keep(*id, entity))
+            .map(|(id, _)| id)
+            .collect();
+        for id in ids {
+            self.remove(id);
+        }
+    }
+
+    pub fn get(&self, id: EntityId) -> Option<&Entity> {
+        let entry = self.slots.get(id.slot as usize)?;
+        if entry.generation == id.generation {
+            entry.entity.as_ref()
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut Entity> {
+        let entry = self.slots.get_mut(id.slot as usize)?;
+        if entry.generation == id.generation {
+            entry.entity.as_mut()
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, &Entity)> {
+        self.slots.iter().enumerate().filter_map(|(slot, entry)| {
+            let id = EntityId {
+                slot: slot as u32,
+                generation: entry.generation,
+            };
+            entry.entity.as_ref().map(|entity| (id, entity))
+        })
+    }
+}
+
+impl Index<EntityId> for EntityList {
+    type Output = Entity;
+
+    fn index(&self, id: EntityId) -> &Entity {
+        self.get(id).expect("no entity with that id")
+    }
+}
+
+impl IndexMut<EntityId> for EntityList {
+    fn index_mut(&mut self, id: EntityId) -> &mut Entity {
+        self.get_mut(id).expect("no entity with that id")
+    }
+}
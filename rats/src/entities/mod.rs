@@ -3,6 +3,7 @@ use video::Size;
 mod brat;
 mod bullet;
 mod factory;
+mod list;
 mod player;
 mod position;
 mod rat;
@@ -10,6 +11,7 @@ mod rat;
 pub use brat::*;
 pub use bullet::*;
 pub use factory::*;
+pub use list::*;
 pub use player::*;
 pub use position::*;
 pub use rat::*;
@@ -50,12 +52,17 @@ impl EntityAction for Entity {
     }
 }
 
-pub type EntityList = Vec<Entity>;
-
-#[derive(Debug, Clone, Copy)]
-pub struct Dimensions {
-    pub rows: Size,
-    pub cols: Size,
+impl Entity {
+    // top left corner and size (in characters) of the space it takes up
+    pub fn footprint(&self) -> (Position, Size) {
+        match self {
+            Entity::Player(player) => (player.pos, 2),
+            Entity::Rat(rat) => (rat.pos, 2),
+            Entity::Brat(brat) => (brat.pos, 1),
+            Entity::Factory(factory) => (factory.pos, 2),
+            Entity::Bullet(bullet) => (bullet.pos, 1),
+        }
+    }
 }
 
 pub type Direction = u8;
@@ -1,6 +1,6 @@
 use super::{dir, Direction, Entity, EntityAction, Position, State};
 use crate::{
-    config::PLAYER_UPDATE_MS,
+    config::config,
     game_context::Action,
     maze::{with_pristine_maze, Maze},
 };
@@ -120,7 +120,8 @@ pub fn render_player(player: &Player, maze: &mut Maze) {
 }
 
 pub fn update_player(player: &Player, update: u32) -> Action {
-    if update < player.update + PLAYER_UPDATE_MS {
+    let update_ms = config().player_update_ms;
+    if update < player.update + update_ms {
         return Action::Nothing;
     }
     let mut player = *player;
@@ -147,28 +148,28 @@ pub fn update_player(player: &Player, update: u32) -> Action {
                 }
             }
             Action::Update(Entity::Player(Player {
-                update: update + PLAYER_UPDATE_MS,
+                update: update + update_ms,
                 cycle: (player.cycle + 1) & 0x3,
                 ..player
             }))
         }
         State::Exploding1 => Action::Update(Entity::Player(Player {
-            update: update + PLAYER_UPDATE_MS / 2,
+            update: update + update_ms / 2,
             state: State::Exploding2,
             ..player
         })),
         State::Exploding2 => Action::Update(Entity::Player(Player {
-            update: update + PLAYER_UPDATE_MS / 2,
+            update: update + update_ms / 2,
             state: State::Exploding3,
             ..player
         })),
         State::Exploding3 => Action::Update(Entity::Player(Player {
-            update: update + PLAYER_UPDATE_MS / 2,
+            update: update + update_ms / 2,
             state: State::Dead,
             ..player
         })),
         State::Dead => Action::Update(Entity::Player(Player {
-            update: update + PLAYER_UPDATE_MS * 2,
+            update: update + update_ms * 2,
             state: State::Alive,
             ..player
         })),
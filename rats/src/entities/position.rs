@@ -3,7 +3,7 @@ use crate::maze::with_pristine_maze;
 use std::cmp::min;
 use video::{Pos, Size};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Position {
     pub row: Pos,
     pub col: Pos,
@@ -123,18 +123,24 @@ impl Position {
         }
     }
 
-    // square of the distance between two points on a torus
-    pub fn distance_squared_to(&self, pos: Position) -> Size {
+    // square of the distance between two points on a torus (or a plane in a
+    // bounded maze)
+    pub fn distance_squared_to(&self, pos: Position) -> u32 {
         let x1 = self.col as i32;
         let x2 = pos.col as i32;
         let y1 = self.row as i32;
         let y2 = pos.row as i32;
-        let (w, h) =
-            with_pristine_maze(|maze| (maze.cols() as i32, maze.rows() as i32));
+        let (w, h) = with_pristine_maze(|maze| {
+            if maze.is_bounded() {
+                (i32::MAX, i32::MAX)
+            } else {
+                (maze.cols() as i32, maze.rows() as i32)
+            }
+        });
         // min(|x1 - x2|, w - |x1 - x2|)^2 + min(|y1 - y2|, h - |y1 - y2|)^2
         let mx = min((x1 - x2).abs(), w - (x1 - x2).abs());
         let my = min((y1 - y2).abs(), h - (y1 - y2).abs());
-        (mx * mx + my * my) as Size
+        (mx * mx + my * my) as u32
     }
 }
 
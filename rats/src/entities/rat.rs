@@ -5,13 +5,13 @@ use video::{
 };
 
 use super::{
-    dir, hit_player_1, Brat, Direction, Entity, EntityAction, Player, Position,
-    State,
+    dir, Brat, Direction, Entity, EntityAction, Player, Position, State,
 };
 use crate::{
-    config::RAT_UPDATE_MS,
-    game_context::{flip_a_coin, random, random_direction, Action},
+    config::config,
+    game_context::{flip_a_coin, random, random_direction, Action, GameRng},
     maze::{with_pristine_maze, Maze},
+    pathfinding::FlowField,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -104,57 +104,74 @@ pub fn render_rat(rat: &Rat, maze: &mut Maze) {
 pub fn update_rat(
     rat: &Rat,
     player: &Player,
-    damage: usize,
+    // (player, damage) if touching a player
+    attack: Option<(usize, usize)>,
     update: u32,
     spawn: bool,
+    field: &FlowField,
+    rng: &mut GameRng,
 ) -> Action {
-    if update < rat.update + RAT_UPDATE_MS {
+    let config = config();
+    let update_ms = config.rat_update_ms;
+    if update < rat.update + update_ms {
         return Action::Nothing;
     }
     let mut rat = *rat;
     match rat.state {
         State::Alive => {
-            if hit_player(rat.pos, player) {
-                return Action::Attack(damage);
+            if let Some((player, damage)) = attack {
+                return Action::Attack(player, damage);
             }
-            if spawn && flip_a_coin() {
+            if spawn && flip_a_coin(rng) {
                 return Action::New(Entity::Brat(Brat {
                     update,
-                    distance: 10 + random(10, 20),
+                    distance: 10 + random(rng, 10, 20),
                     pos: rat.pos,
-                    dir: random_direction(),
+                    dir: random_direction(rng),
                     state: State::Alive,
                     cycle: 0,
                 }));
             }
-            if let Some(dir) = player_dir(rat.pos, player.pos) {
+            let smart = config.rat_smarts > 0
+                && random(rng, 1, 100) <= config.rat_smarts;
+            let hunt = if smart {
+                field.direction(rat.pos)
+            } else {
+                None
+            };
+            if let Some(dir) = hunt {
                 rat.dir = dir;
-            }
-            if rat.distance == 0 || !rat.can_advance(rat.dir) {
-                rat.dir = random_direction();
-                rat.distance = random(5, 15);
+                rat.advance(dir);
             } else {
-                rat.advance(rat.dir);
-                rat.distance -= 1;
+                if let Some(dir) = player_dir(rat.pos, player.pos) {
+                    rat.dir = dir;
+                }
+                if rat.distance == 0 || !rat.can_advance(rat.dir) {
+                    rat.dir = random_direction(rng);
+                    rat.distance = random(rng, 5, 15);
+                } else {
+                    rat.advance(rat.dir);
+                    rat.distance -= 1;
+                }
             }
             Action::Update(Entity::Rat(Rat {
-                update: update + RAT_UPDATE_MS,
+                update: update + update_ms,
                 cycle: (rat.cycle + 1) & 0x3,
                 ..rat
             }))
         }
         State::Exploding1 => Action::Update(Entity::Rat(Rat {
-            update: update + RAT_UPDATE_MS / 2,
+            update: update + update_ms / 2,
             state: State::Exploding2,
             ..rat
         })),
         State::Exploding2 => Action::Update(Entity::Rat(Rat {
-            update: update + RAT_UPDATE_MS / 2,
+            update: update + update_ms / 2,
             state: State::Exploding3,
             ..rat
         })),
         State::Exploding3 => Action::Update(Entity::Rat(Rat {
-            update: update + RAT_UPDATE_MS / 2,
+            update: update + update_ms / 2,
             state: State::Dead,
             ..rat
         })),
@@ -162,24 +179,6 @@ pub fn update_rat(
     }
 }
 
-fn hit_player(pos: Position, player: &Player) -> bool {
-    if hit_player_1(pos, player) {
-        return true;
-    }
-    let mut pos2 = pos;
-    pos2.move_right(1);
-    if hit_player_1(pos2, player) {
-        return true;
-    }
-    let mut pos2 = pos;
-    pos2.move_down(1);
-    if hit_player_1(pos2, player) {
-        return true;
-    }
-    pos2.move_right(1);
-    hit_player_1(pos2, player)
-}
-
 pub fn player_dir(pos: Position, player_pos: Position) -> Option<Direction> {
     with_pristine_maze(|maze| {
         if pos.distance_squared_to(player_pos) < 25 * 25 {
@@ -0,0 +1,34 @@
+use thiserror::Error;
+use video::VideoError;
+
+pub type Result<T> = std::result::Result<T, GameError>;
+
+#[derive(Error, Debug)]
+pub enum GameError {
+    #[error("{0}")]
+    Video(#[from] VideoError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("replay error: {0}")]
+    Replay(String),
+
+    #[error("config error: {0}")]
+    Config(String),
+
+    #[error("maze file error: {0}")]
+    Maze(String),
+}
+
+pub fn replay_error<S: AsRef<str>>(error: S) -> GameError {
+    GameError::Replay(error.as_ref().to_string())
+}
+
+pub fn config_error<S: AsRef<str>>(error: S) -> GameError {
+    GameError::Config(error.as_ref().to_string())
+}
+
+pub fn maze_error<S: AsRef<str>>(error: S) -> GameError {
+    GameError::Maze(error.as_ref().to_string())
+}
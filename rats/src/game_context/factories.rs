@@ -1,66 +1,131 @@
-use super::GameContext;
+use super::{factory_position, GameContext};
 use crate::{
     entities::{Entity, Factory, Position, State},
-    maze::{MAZE_CELL_COLS, MAZE_CELL_ROWS, PRISTINE_MAZE},
+    maze::PRISTINE_MAZE,
+    pathfinding::reachable,
 };
-use rand::{distributions::Uniform, thread_rng, Rng};
-use std::time::Instant;
-use video::SizeWrapping;
+use rand::{distributions::Uniform, Rng};
+use video::Pos;
 
 impl GameContext {
-    pub fn generate_factories(&mut self, count: usize) {
+    // Random positions for up to count rat factories. Factories only go where
+    // the players can get to them, which is found by searching the maze from
+    // the players' starting positions, so there may be room for fewer of them
+    // than asked for.
+    pub fn random_factories(&mut self, count: usize) -> Vec<Position> {
+        let starts = self.start_positions();
+        let starts = &starts[..self.players.len()];
         PRISTINE_MAZE.with(|maze| {
             let maze = maze.borrow();
-            let mut rng = thread_rng();
-            let row_distribution = Uniform::new_inclusive(0, maze.rows() - 1);
-            let col_distribution = Uniform::new_inclusive(0, maze.cols() - 1);
-            let cycle_distribution = Uniform::new_inclusive(0, 1);
-            let mut generated = 0;
-            let mut positions = vec![];
-            // we know where the player starts
-            let player_pos = Position {
-                row: MAZE_CELL_ROWS / 2,
-                col: MAZE_CELL_COLS / 2,
-            };
-            let time = Instant::now();
-            // don't spend more than a second on this
-            'again: while generated < count && time.elapsed().as_millis() < 1000
-            {
-                let row1 = rng.sample(row_distribution);
-                let col1 = rng.sample(col_distribution);
-                let row2 = row1.inc(maze.rows());
-                let col2 = col1.inc(maze.cols());
-                if maze.is_wall(row1, col1)
-                    || maze.is_wall(row1, col2)
-                    || maze.is_wall(row2, col1)
-                    || maze.is_wall(row2, col2)
-                {
-                    continue 'again;
+            // factories must be at least 15 characters away from the players
+            let mut candidates: Vec<Position> = reachable(&maze, starts)
+                .positions()
+                .into_iter()
+                .filter(|pos| {
+                    starts
+                        .iter()
+                        .all(|start| pos.distance_squared_to(*start) >= 225)
+                })
+                .collect();
+            let mut positions: Vec<Position> = vec![];
+            while positions.len() < count && !candidates.is_empty() {
+                let index = self.rng.gen_range(0..candidates.len());
+                let pos = candidates.swap_remove(index);
+                // factories must be at least 5 characters away from other
+                // factories
+                if positions.iter().all(|p| pos.distance_squared_to(*p) >= 25) {
+                    positions.push(pos);
                 }
-                let pos = Position {
-                    row: row1,
-                    col: col1,
-                };
-                // factories must be at least 15 characters away from the player
-                if pos.distance_squared_to(player_pos) < 225 {
-                    continue 'again;
-                }
-                // factories must be at least 5 characters away from other factories
-                for p in positions.iter() {
-                    if pos.distance_squared_to(*p) < 25 {
-                        continue 'again;
+            }
+            positions
+        })
+    }
+
+    // maze cells (from a maze file) with factories that the players can't
+    // get to
+    pub fn unreachable_factories(
+        &self,
+        cells: &[(Pos, Pos)],
+    ) -> Vec<(Pos, Pos)> {
+        let starts = self.start_positions();
+        let starts = &starts[..self.players.len()];
+        PRISTINE_MAZE.with(|maze| {
+            let field = reachable(&maze.borrow(), starts);
+            cells
+                .iter()
+                .filter(|&&(row, col)| {
+                    field.distance(factory_position(row, col)).is_none()
+                })
+                .copied()
+                .collect()
+        })
+    }
+
+    pub fn add_factories(&mut self, positions: &[Position]) {
+        let update = self.elapsed();
+        let cycle_distribution = Uniform::new_inclusive(0, 1);
+        for &pos in positions {
+            self.entities.insert(Entity::Factory(Factory {
+                update,
+                pos,
+                state: State::Alive,
+                cycle: self.rng.sample(cycle_distribution),
+            }));
+        }
+        self.live_factories = positions.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clock::SimulatedClock, config::config, levels::Levels,
+        maze::MazeAlgorithm,
+    };
+    use video::InitOptions;
+
+    fn context(seed: u64, density: usize, players: usize) -> GameContext {
+        let levels = Levels::new(
+            Default::default(),
+            MazeAlgorithm::default(),
+            false,
+            &InitOptions::new().density(Some(density)),
+            0,
+            0,
+            config(),
+        );
+        GameContext::create(
+            players,
+            levels,
+            10,
+            5,
+            Some(seed),
+            Box::new(SimulatedClock::new(16)),
+            None,
+        )
+    }
+
+    #[test]
+    fn players_can_get_to_every_factory() {
+        for density in [0, 50, 75, 90, 100] {
+            for seed in 1..=5 {
+                for players in 1..=2 {
+                    let mut context = context(seed, density, players);
+                    let starts = context.start_positions();
+                    let field = PRISTINE_MAZE.with(|maze| {
+                        reachable(&maze.borrow(), &starts[..players])
+                    });
+                    let factories = context.random_factories(20);
+                    assert!(!factories.is_empty());
+                    for pos in factories {
+                        assert!(
+                            field.distance(pos).is_some(),
+                            "density {density}, seed {seed}, {players} players"
+                        );
                     }
                 }
-                positions.push(pos);
-                self.entities.push(Entity::Factory(Factory {
-                    update: self.elapsed(),
-                    pos,
-                    state: State::Alive,
-                    cycle: rng.sample(cycle_distribution),
-                }));
-                generated += 1;
             }
-            self.live_factories = generated;
-        });
+        }
     }
 }
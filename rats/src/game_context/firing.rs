@@ -1,19 +1,19 @@
-use std::time::Instant;
-
 use super::GameContext;
-use crate::entities::{
-    dir, Bullet, Direction, Entity, EntityAction, Position, State,
+use crate::{
+    config::config,
+    entities::{dir, Bullet, Direction, Entity, EntityAction, Position, State},
 };
-use video::SizeWrapping;
+use video::{SizeWrapping, Sound};
 
 impl GameContext {
-    pub fn fire(&mut self) {
-        let player = self.get_player();
-        if player.state != State::Alive || self.next_fire_time > Instant::now()
+    pub fn fire(&mut self, index: usize) {
+        let player = self.get_player(index);
+        if player.state != State::Alive
+            || self.players[index].next_fire_time > self.elapsed()
         {
             return;
         }
-        let dir = self.effective_firing_dir();
+        let dir = self.effective_firing_dir(index);
         let (row, col) = (player.pos.row, player.pos.col);
         let (rows, cols) = (self.maze.rows(), self.maze.cols());
         if let Some((row, col)) = match dir {
@@ -30,40 +30,49 @@ impl GameContext {
             if self.maze.is_wall(row, col) {
                 return;
             }
-            self.bullet_fire_start = Instant::now();
-            self.next_fire_time =
-                self.bullet_fire_start + self.bullet_firing_time;
+            self.players[index].next_fire_time =
+                self.elapsed() + config().player_fire_rate_ms;
+            self.shots_fired += 1;
             let pos = Position { row, col };
-            for entity in self.entities.iter_mut().skip(1) {
-                if entity.hit(pos) {
-                    entity.explode();
-                    return;
+            let target = self.spatial.at(pos).find(|&id| {
+                self.entities.get(id).is_some_and(|entity| {
+                    !matches!(entity, Entity::Player(_)) && entity.hit(pos)
+                })
+            });
+            if let Some(id) = target {
+                // point blank
+                let entity = &mut self.entities[id];
+                if !matches!(entity, Entity::Bullet(_)) {
+                    self.shots_hit += 1;
                 }
+                entity.explode();
+                return;
             }
-            self.video.play_gunshot();
-            self.entities.push(Entity::Bullet(Bullet {
+            self.sounds.push(Sound::Gunshot);
+            self.entities.insert(Entity::Bullet(Bullet {
                 update: self.elapsed(),
                 lifetime: 0,
                 pos,
                 dir,
                 state: State::Alive,
+                player: index,
             }));
         }
     }
 
-    pub fn start_firing(&mut self, dir: Direction) {
-        if self.firing_dir & dir == 0 {
-            self.firing_dir |= dir;
-            self.fire();
+    pub fn start_firing(&mut self, player: usize, dir: Direction) {
+        if self.players[player].firing_dir & dir == 0 {
+            self.players[player].firing_dir |= dir;
+            self.fire(player);
         }
     }
 
-    pub fn stop_firing(&mut self, dir: Direction) {
-        self.firing_dir &= !dir;
+    pub fn stop_firing(&mut self, player: usize, dir: Direction) {
+        self.players[player].firing_dir &= !dir;
     }
 
-    pub fn effective_firing_dir(&self) -> Direction {
-        let mut dir = self.firing_dir;
+    pub fn effective_firing_dir(&self, player: usize) -> Direction {
+        let mut dir = self.players[player].firing_dir;
         if (dir & dir::UP) != 0 && (dir & dir::DOWN) != 0 {
             dir &= !(dir::UP | dir::DOWN);
         }
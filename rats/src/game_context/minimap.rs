@@ -0,0 +1,92 @@
+use super::GameContext;
+use crate::{
+    entities::{Entity, Position},
+    maze::{MAZE_CELL_COLS, MAZE_CELL_ROWS, PRISTINE_MAZE},
+};
+use std::cmp::max;
+use video::{
+    Buffer, Viewport, ATTR_DIM, ATTR_NONE, MINIMAP_EMPTY, MINIMAP_FACTORY,
+    MINIMAP_LEFT_WALL, MINIMAP_PLAYER, MINIMAP_RATS, MINIMAP_TOP_WALL,
+};
+
+// a minimap cell with at least this many rats (and brats) per maze cell is
+// shown brighter than one with fewer
+const MANY_RATS: usize = 3;
+
+impl GameContext {
+    // Map of the whole maze in the top right corner of the screen with one
+    // character for every maze cell, or for a square of maze cells if the
+    // maze is too big to fit into a quarter of the screen. It shows where the
+    // players and rat factories are and where the rats are gathering.
+    pub fn render_minimap(&self, vbuf: &mut Buffer) {
+        let (cell_rows, cell_cols) =
+            (self.maze.cell_rows(), self.maze.cell_cols());
+        let max_rows = max((vbuf.rows - 2) / 2, 3) - 2;
+        let max_cols = max(vbuf.cols / 2, 3) - 2;
+        let scale =
+            max(cell_rows.div_ceil(max_rows), cell_cols.div_ceil(max_cols));
+        let rows = cell_rows.div_ceil(scale);
+        let cols = cell_cols.div_ceil(scale);
+
+        let index = |pos: Position| {
+            let row = pos.row / (MAZE_CELL_ROWS + 1) / scale;
+            let col = pos.col / (MAZE_CELL_COLS + 1) / scale;
+            (row * cols + col) as usize
+        };
+        let mut players = vec![false; rows as usize * cols as usize];
+        let mut factories = players.clone();
+        let mut rats = vec![0; players.len()];
+        for (_, entity) in self.entities.iter() {
+            match entity {
+                Entity::Player(player) => players[index(player.pos)] = true,
+                Entity::Factory(factory) => {
+                    factories[index(factory.pos)] = true
+                }
+                Entity::Rat(rat) => rats[index(rat.pos)] += 1,
+                Entity::Brat(brat) => rats[index(brat.pos)] += 1,
+                Entity::Bullet(_) => {}
+            }
+        }
+
+        let mut map = Buffer::new(rows, cols);
+        PRISTINE_MAZE.with(|maze| {
+            let maze = maze.borrow();
+            for row in 0..rows {
+                for col in 0..cols {
+                    // walls of the top left maze cell
+                    let maze_row = row * scale * (MAZE_CELL_ROWS + 1);
+                    let maze_col = col * scale * (MAZE_CELL_COLS + 1);
+                    let mut walls = 0;
+                    if maze.is_wall(maze_row, maze_col + 1) {
+                        walls |= MINIMAP_TOP_WALL;
+                    }
+                    if maze.is_wall(maze_row + 1, maze_col) {
+                        walls |= MINIMAP_LEFT_WALL;
+                    }
+                    let index = (row * cols + col) as usize;
+                    let many_rats = MANY_RATS * (scale * scale) as usize;
+                    let (ch, attr) = if players[index] {
+                        (MINIMAP_PLAYER, ATTR_NONE)
+                    } else if factories[index] {
+                        (MINIMAP_FACTORY, ATTR_NONE)
+                    } else if rats[index] >= many_rats {
+                        (MINIMAP_RATS, ATTR_NONE)
+                    } else if rats[index] > 0 {
+                        (MINIMAP_RATS, ATTR_DIM)
+                    } else {
+                        (MINIMAP_EMPTY, ATTR_NONE)
+                    };
+                    map.set_chattr(row, col, ch + walls, attr);
+                }
+            }
+        });
+
+        let viewport = Viewport::new(
+            2,
+            vbuf.cols.saturating_sub(cols + 2),
+            rows + 2,
+            cols + 2,
+        );
+        map.copy_viewport(0, 0, vbuf, viewport, Some(ATTR_NONE));
+    }
+}
@@ -1,27 +1,28 @@
 use crate::{
-    config::PLAYER_FIRE_RATE_NS,
-    entities::{dir, Direction, Entity, EntityList, Player, Position, State},
-    maze::{Maze, MAZE_CELL_COLS, MAZE_CELL_ROWS, PRISTINE_MAZE},
+    clock::Clock,
+    config::set_config,
+    entities::{
+        dir, Direction, Entity, EntityId, EntityList, Player, Position, State,
+    },
+    levels::Levels,
+    maze::{Maze, MazeFile, MAZE_CELL_COLS, MAZE_CELL_ROWS, PRISTINE_MAZE},
+    pathfinding::FlowField,
+    spatial::SpatialIndex,
 };
 use rand::{
     distributions::{uniform::SampleUniform, Uniform},
-    thread_rng, Rng,
+    rngs::StdRng,
+    thread_rng, Rng, SeedableRng,
 };
-use std::{
-    cmp::max,
-    fmt::Display,
-    time::{Duration, Instant},
-};
-use video::{InitOptions, Pos, Result, Video};
+use std::{cmp::max, fmt::Display, time::Instant};
+use video::{Pos, Sound};
 
 mod factories;
 mod firing;
+mod minimap;
 mod render;
 mod update;
 
-pub use factories::*;
-pub use firing::*;
-pub use render::*;
 pub use update::*;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -31,6 +32,8 @@ pub enum GameState {
     Finished,
     Quit,
     Restart,
+    EnterInitials,
+    Settings,
 }
 
 impl Display for GameState {
@@ -41,23 +44,66 @@ impl Display for GameState {
             GameState::Finished => write!(f, "FINISHED")?,
             GameState::Quit => write!(f, "QUIT")?,
             GameState::Restart => write!(f, "RESTART")?,
+            GameState::EnterInitials => write!(f, "INITIALS")?,
+            GameState::Settings => write!(f, "SETTINGS")?,
         }
         Ok(())
     }
 }
 
+// most players at once (local co-op)
+pub const MAX_PLAYERS: usize = 2;
+
+// times a random maze is generated before settling for fewer rat factories
+const MAZE_ATTEMPTS: usize = 10;
+
+// where each player starts every level
+const START_POSITIONS: [Position; MAX_PLAYERS] = [
+    Position {
+        row: (MAZE_CELL_ROWS / 2) as Pos,
+        col: (MAZE_CELL_COLS / 2) as Pos,
+    },
+    Position {
+        row: (MAZE_CELL_ROWS / 2) as Pos,
+        col: (MAZE_CELL_COLS / 2 + 4) as Pos,
+    },
+];
+
+// everything that belongs to one of the players, lives are shared
+pub struct PlayerInfo {
+    pub id: EntityId,
+    pub firing_dir: Direction,
+    pub next_fire_time: u32,
+    pub health: usize,
+    pub score: usize,
+    pub life_start: u32,
+}
+
+// every random decision in the game is drawn from a single seeded RNG
+pub type GameRng = StdRng;
+
 pub struct GameContext {
     pub game_state: GameState,
+    pub seed: u64,
+    pub rng: GameRng,
     pub diagnostics: bool,
-    pub video: Video,
+    pub minimap: bool,
+    pub sounds: Vec<Sound>,
+    pub clock: Box<dyn Clock>,
+    pub ticks: u64,
     pub start: Instant,
     pub frames: u32,
     pub maze: Maze,
-    pub firing_dir: Direction,
-    pub bullet_fire_start: Instant,
-    pub bullet_firing_time: Duration,
-    pub next_fire_time: Instant,
+    // hand-drawn maze used on every level instead of generated ones
+    pub maze_file: Option<MazeFile>,
+    pub next_rat_spawn: u32,
+    pub next_brat_spawn: u32,
     pub entities: EntityList,
+    pub players: Vec<PlayerInfo>,
+    // give each player half of the screen in co-op games
+    pub split_screen: bool,
+    pub flow_field: FlowField,
+    pub spatial: SpatialIndex,
     pub live_factories: usize,
     pub dead_factories: usize,
     pub live_rats: usize,
@@ -67,45 +113,58 @@ pub struct GameContext {
     pub new_rats: usize,
     pub new_brats: usize,
     pub super_boom: usize,
+    // the whole team's score
     pub score: usize,
-    pub health: usize,
     pub rat_damage: usize,
     pub brat_damage: usize,
     pub players_left: usize,
     pub players_dead: usize,
     pub time: usize,
+    // end of game statistics
+    pub shots_fired: usize,
+    pub shots_hit: usize,
+    pub damage_from_rats: usize,
+    pub damage_from_brats: usize,
+    pub longest_life: u32,
+    pub levels: Levels,
+    pub level: usize,
+    pub density: usize,
+    pub factories: usize,
+    pub initials: String,
 }
 
 impl GameContext {
     pub fn create(
-        opts: InitOptions,
+        players: usize,
+        levels: Levels,
         rat_damage: usize,
         brat_damage: usize,
-    ) -> Result<GameContext> {
-        let video = video::init(opts)?;
-        let maze_rows = max(
-            (video.rows() - 2) / MAZE_CELL_ROWS,
-            opts.maze_height.unwrap_or(15),
-        );
-        let maze_cols =
-            max(video.cols() / MAZE_CELL_COLS, opts.maze_width.unwrap_or(15));
-        PRISTINE_MAZE.with(|maze| {
-            let mut maze = maze.borrow_mut();
-            *maze = Maze::new(maze_rows, maze_cols);
-            maze.generate(opts.density.unwrap_or(75));
-        });
+        seed: Option<u64>,
+        clock: Box<dyn Clock>,
+        maze_file: Option<MazeFile>,
+    ) -> GameContext {
+        let seed = seed.unwrap_or_else(|| thread_rng().gen());
+        let first = levels.level(1);
         let mut context = GameContext {
             game_state: GameState::Running,
+            seed,
+            rng: GameRng::seed_from_u64(seed),
             diagnostics: false,
-            video,
+            minimap: false,
+            sounds: vec![],
+            clock,
+            ticks: 0,
             start: Instant::now(),
             frames: 0,
-            maze: Maze::new(maze_rows, maze_cols),
-            firing_dir: dir::NONE,
-            bullet_fire_start: Instant::now(),
-            bullet_firing_time: Duration::new(0, PLAYER_FIRE_RATE_NS),
-            next_fire_time: Instant::now(),
+            maze: Maze::new(first.maze_height, first.maze_width, first.bounded),
+            maze_file,
+            next_rat_spawn: 0,
+            next_brat_spawn: 0,
             entities: EntityList::new(),
+            players: vec![],
+            split_screen: false,
+            flow_field: FlowField::new(),
+            spatial: SpatialIndex::new(),
             live_factories: 0,
             dead_factories: 0,
             live_rats: 0,
@@ -116,86 +175,260 @@ impl GameContext {
             new_brats: 0,
             super_boom: 0,
             score: 0,
-            health: 100,
             rat_damage: rat_damage.clamp(0, 100),
             brat_damage: brat_damage.clamp(0, 100),
-            players_left: 3,
+            players_left: 0,
             players_dead: 0,
             time: 0,
+            shots_fired: 0,
+            shots_hit: 0,
+            damage_from_rats: 0,
+            damage_from_brats: 0,
+            longest_life: 0,
+            levels,
+            level: 1,
+            density: first.density,
+            factories: first.factories,
+            initials: String::new(),
         };
-        context.entities.push(Entity::Player(Player {
-            update: context.elapsed(),
-            pos: Position {
-                row: (MAZE_CELL_ROWS / 2) as Pos,
-                col: (MAZE_CELL_COLS / 2) as Pos,
-            },
-            dir: dir::NONE,
-            stop_dir: dir::DOWN,
-            state: State::Alive,
-            cycle: 0,
-        }));
-        context.generate_factories(opts.factories.unwrap_or(5).clamp(1, 100));
-        Ok(context)
-    }
-
-    pub fn new_game(&mut self, opts: InitOptions) {
+        let starts = context.start_positions();
+        for pos in starts.into_iter().take(players.max(1)) {
+            let id = context.entities.insert(Entity::Player(Player {
+                update: context.elapsed(),
+                pos,
+                dir: dir::NONE,
+                stop_dir: dir::DOWN,
+                state: State::Alive,
+                cycle: 0,
+            }));
+            context.players.push(PlayerInfo {
+                id,
+                firing_dir: dir::NONE,
+                next_fire_time: 0,
+                health: 100,
+                score: 0,
+                life_start: 0,
+            });
+        }
+        context.players_left = 3 * context.players.len();
+        context.start_level(1);
+        context
+    }
+
+    pub fn new_game(&mut self) {
         self.game_state = GameState::Running;
-        self.live_factories = 0;
         self.dead_factories = 0;
-        self.live_rats = 0;
         self.dead_rats = 0;
-        self.live_brats = 0;
         self.dead_brats = 0;
-        self.new_rats = 0;
-        self.new_brats = 0;
         self.super_boom = 0;
         self.score = 0;
-        self.health = 100;
-        self.players_left = 3;
+        self.players_left = 3 * self.players.len();
         self.players_dead = 0;
-        self.entities.truncate(1);
-        PRISTINE_MAZE.with(|maze| {
-            let mut maze = maze.borrow_mut();
-            maze.buffer.clear();
-            maze.generate(opts.density.unwrap_or(75));
-        });
-        self.generate_factories(opts.factories.unwrap_or(5).clamp(1, 100));
+        self.initials.clear();
+        self.clock.reset();
+        self.time = 0;
+        self.shots_fired = 0;
+        self.shots_hit = 0;
+        self.damage_from_rats = 0;
+        self.damage_from_brats = 0;
+        self.longest_life = 0;
+        for player in self.players.iter_mut() {
+            player.firing_dir = dir::NONE;
+            player.next_fire_time = 0;
+            player.health = 100;
+            player.score = 0;
+            player.life_start = 0;
+        }
+        self.start_level(1);
+    }
+
+    // the maze, rat factories and game speed all depend on the level, score,
+    // lives and statistics carry over from one level to the next
+    pub fn start_level(&mut self, number: usize) {
+        let level = self.levels.level(number);
+        set_config(level.config);
+        self.level = number;
+        self.density = level.density;
+        self.factories = level.factories;
+        let now = self.elapsed();
+        self.next_rat_spawn = now;
+        self.next_brat_spawn = now + level.config.brat_spawn_seconds * 1000;
+        self.live_factories = 0;
+        self.live_rats = 0;
+        self.live_brats = 0;
+        self.new_rats = 0;
+        self.new_brats = 0;
+        self.entities
+            .retain(|_, entity| matches!(entity, Entity::Player(_)));
+        let players = self.players.len();
+        for (index, pos) in
+            self.start_positions().into_iter().take(players).enumerate()
+        {
+            let player = self.get_player_mut(index);
+            player.pos = pos;
+            player.update = now;
+        }
+        let (maze_height, maze_width) = match &self.maze_file {
+            Some(maze_file) => (maze_file.rows(), maze_file.cols()),
+            None => (level.maze_height, level.maze_width),
+        };
+        if self.maze.cell_rows() != maze_height
+            || self.maze.cell_cols() != maze_width
+            || self.maze.is_bounded() != level.bounded
+        {
+            self.maze = Maze::new(maze_height, maze_width, level.bounded);
+        }
+        // random mazes are generated again (a few times) if the players can't
+        // get to enough places to put all of the rat factories
+        let mut attempts = 0;
+        let factories = loop {
+            attempts += 1;
+            PRISTINE_MAZE.with(|maze| {
+                let mut maze = maze.borrow_mut();
+                *maze = Maze::new(maze_height, maze_width, level.bounded);
+                match &self.maze_file {
+                    Some(maze_file) => maze.draw(&maze_file.grid),
+                    None => maze.generate(
+                        level.algorithm,
+                        level.density,
+                        &mut self.rng,
+                    ),
+                }
+            });
+            match self.maze_file.as_ref().map(|file| file.factories.clone()) {
+                // maze files without any factories get random ones
+                Some(cells) if !cells.is_empty() => {
+                    for (row, col) in self.unreachable_factories(&cells) {
+                        println!(
+                            "maze file warning: the factory in maze cell \
+                            {},{} can't be reached",
+                            row + 1,
+                            col + 1
+                        );
+                    }
+                    break cells
+                        .iter()
+                        .map(|&(row, col)| factory_position(row, col))
+                        .collect();
+                }
+                _ => {
+                    let factories = self.random_factories(level.factories);
+                    if factories.len() == level.factories
+                        || self.maze_file.is_some()
+                        || attempts == MAZE_ATTEMPTS
+                    {
+                        if factories.len() < level.factories {
+                            println!(
+                                "only {} of {} rat factories fit in the maze",
+                                factories.len(),
+                                level.factories
+                            );
+                        }
+                        break factories;
+                    }
+                }
+            }
+        };
+        self.flow_field.reset();
+        self.add_factories(&factories);
+        self.index_entities();
+    }
+
+    // where each player starts every level
+    pub fn start_positions(&self) -> Vec<Position> {
+        let mut starts = START_POSITIONS.to_vec();
+        if let Some(maze_file) = &self.maze_file {
+            for (player, start) in maze_file.starts.iter().enumerate() {
+                if let Some((cell_row, cell_col)) = *start {
+                    starts[player] = start_position(player, cell_row, cell_col);
+                }
+            }
+        }
+        starts
+    }
+
+    // the current maze along with its factories and starting positions, in
+    // maze file form
+    pub fn to_maze_file(&self) -> MazeFile {
+        let grid = PRISTINE_MAZE.with(|maze| maze.borrow().grid());
+        let mut factories = vec![];
+        for (_, entity) in self.entities.iter() {
+            if let Entity::Factory(factory) = entity {
+                if factory.state == State::Alive {
+                    factories
+                        .push(Maze::cell_at(factory.pos.row, factory.pos.col));
+                }
+            }
+        }
+        let mut starts = [None; MAX_PLAYERS];
+        for (start, pos) in starts.iter_mut().zip(self.start_positions()) {
+            *start = Some(Maze::cell_at(pos.row, pos.col));
+        }
+        MazeFile {
+            grid,
+            factories,
+            starts,
+        }
+    }
+
+    // score minus a point for every second it took
+    pub fn final_score(&self) -> usize {
+        self.score.saturating_sub(self.time)
     }
 
+    // percentage of shots that hit a rat, brat or factory
+    pub fn accuracy(&self) -> Option<usize> {
+        (self.shots_hit * 100).checked_div(self.shots_fired)
+    }
+
+    // longest time (in milliseconds) any player survived without dying
+    pub fn longest_life(&self) -> u32 {
+        let mut longest_life = self.longest_life;
+        for (index, player) in self.players.iter().enumerate() {
+            if self.get_player(index).state == State::Alive {
+                longest_life =
+                    max(longest_life, self.elapsed() - player.life_start);
+            }
+        }
+        longest_life
+    }
+
+    // game time in milliseconds
     pub fn elapsed(&self) -> u32 {
-        self.start.elapsed().as_millis() as u32
+        self.clock.now()
+    }
+
+    pub fn player_position(&self, player: usize) -> Position {
+        self.get_player(player).pos
     }
 
-    pub fn player_position(&self) -> Position {
-        self.get_player().pos
+    // index of the player with this entity id (if it is one)
+    pub fn player_index(&self, id: EntityId) -> Option<usize> {
+        self.players.iter().position(|player| player.id == id)
     }
 
-    pub fn get_player(&self) -> &Player {
-        match self.entities.get(0).expect("get_player: can't get player") {
-            Entity::Player(player) => player,
+    pub fn get_player(&self, player: usize) -> &Player {
+        match self.entities.get(self.players[player].id) {
+            Some(Entity::Player(player)) => player,
             _ => panic!("get_player: player is not a Player"),
         }
     }
 
-    pub fn get_player_mut(&mut self) -> &mut Player {
-        match self
-            .entities
-            .get_mut(0)
-            .expect("get_player_mut: can't get player")
-        {
-            Entity::Player(player) => player,
+    pub fn get_player_mut(&mut self, player: usize) -> &mut Player {
+        match self.entities.get_mut(self.players[player].id) {
+            Some(Entity::Player(player)) => player,
             _ => panic!("get_player_mut: player is not a Player"),
         }
     }
 
-    pub fn start(&mut self, dir: Direction) {
-        let player = self.get_player_mut();
+    pub fn start(&mut self, player: usize, dir: Direction) {
+        let player = self.get_player_mut(player);
         player.dir |= dir;
         player.stop_dir = dir::stop_dir(dir);
     }
 
-    pub fn stop(&mut self, dir: Direction) {
-        let player = self.get_player_mut();
+    pub fn stop(&mut self, player: usize, dir: Direction) {
+        let player = self.get_player_mut(player);
         player.dir = if dir == dir::NONE {
             dir
         } else {
@@ -204,14 +437,32 @@ impl GameContext {
     }
 }
 
-pub fn random<T: SampleUniform>(low: T, high: T) -> T {
-    let mut rng = thread_rng();
+// the middle of a maze cell, player 2 starts a little to the right of player
+// 1 in case they're in the same cell
+pub fn start_position(player: usize, cell_row: Pos, cell_col: Pos) -> Position {
+    let (row, col) = Maze::cell_position(cell_row, cell_col);
+    Position {
+        row: row + MAZE_CELL_ROWS / 2,
+        col: col + MAZE_CELL_COLS / 2 + 4 * player as Pos,
+    }
+}
+
+// the middle of a maze cell
+pub fn factory_position(cell_row: Pos, cell_col: Pos) -> Position {
+    let (row, col) = Maze::cell_position(cell_row, cell_col);
+    Position {
+        row: row + MAZE_CELL_ROWS / 2,
+        col: col + MAZE_CELL_COLS / 2,
+    }
+}
+
+pub fn random<T: SampleUniform>(rng: &mut GameRng, low: T, high: T) -> T {
     let distribution = Uniform::new_inclusive(low, high);
     rng.sample(distribution)
 }
 
-pub fn random_direction() -> Direction {
-    match random(0, 3) {
+pub fn random_direction(rng: &mut GameRng) -> Direction {
+    match random(rng, 0, 3) {
         0 => dir::UP,
         1 => dir::DOWN,
         2 => dir::LEFT,
@@ -219,6 +470,6 @@ pub fn random_direction() -> Direction {
     }
 }
 
-pub fn flip_a_coin() -> bool {
-    random(0, 99) > 50
+pub fn flip_a_coin(rng: &mut GameRng) -> bool {
+    random(rng, 0, 99) > 50
 }
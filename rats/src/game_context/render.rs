@@ -1,69 +1,91 @@
 use crate::{
     entities::{
         render_brat, render_bullet, render_factory, render_player, render_rat,
-        Entity,
+        Entity, Position,
     },
     game_context::GameContext,
+    high_scores::{HighScores, INITIALS_LENGTH},
     maze::{Maze, PRISTINE_MAZE},
-    Result,
 };
-use sdl2::render::Texture;
 use video::{
-    ATTR_NONE, ATTR_REVERSE, BRATS_UP_A1, FACTORY_A2, PLAYER_DOWN, RATS_UP_A1,
+    Buffer, Pos, Size, Viewport, ATTR_NONE, ATTR_REVERSE, BRATS_UP_A1,
+    FACTORY_A2, PLAYER_DOWN, RATS_UP_A1,
 };
 
 use super::GameState;
 
 impl GameContext {
+    // render the current frame into a video buffer
     pub fn render_frame(
         &mut self,
-        textures: &[Texture],
+        vbuf: &mut Buffer,
         classic: bool,
-    ) -> Result<()> {
+        high_scores: &HighScores,
+    ) {
         // start with a clear video buffer and pristine maze
-        self.video.buffer.clear();
+        vbuf.clear();
         PRISTINE_MAZE.with(|maze| {
             let maze = maze.borrow();
             maze.buffer.copy_to(&mut self.maze.buffer)
         });
 
-        // render all entities in reverse order so that player
-        // and rat factories are rendered last
-        for entity in self.entities.iter_mut().rev() {
+        // rat factories and then players are rendered last so that they
+        // aren't hidden by anything else
+        let mut entities: Vec<&Entity> =
+            self.entities.iter().map(|(_, entity)| entity).collect();
+        entities.sort_by_key(|entity| match entity {
+            Entity::Player(_) => 2,
+            Entity::Factory(_) => 1,
+            _ => 0,
+        });
+        for entity in entities {
             render_entity(entity, &mut self.maze);
         }
 
-        // copy the visible portion of our current maze into the video buffer
-        let mut start_pos = self.player_position();
-        start_pos.move_up((self.video.buffer.rows - 2) / 2);
-        start_pos.move_left(self.video.buffer.cols / 2);
-        self.maze.buffer.copy_buffer(
-            start_pos.row,
-            start_pos.col,
-            &mut self.video.buffer,
-            2,
-        );
+        // copy the visible portion of our current maze into the video buffer,
+        // co-op games either keep both players in one view or split the
+        // screen in two with a status line for both players in between
+        let view_rows = vbuf.rows - 2;
+        let start_pos = if self.players.len() == 1 {
+            let view = Viewport::new(2, 0, view_rows, vbuf.cols);
+            self.render_view(vbuf, self.player_position(0), view)
+        } else if self.split_screen {
+            let top_rows = (view_rows - 1) / 2;
+            let bottom_rows = view_rows - 1 - top_rows;
+            let top = Viewport::new(2, 0, top_rows, vbuf.cols);
+            let bottom = Viewport::new(3 + top_rows, 0, bottom_rows, vbuf.cols);
+            self.render_view(vbuf, self.player_position(1), bottom);
+            self.render_player_status(vbuf, 2 + top_rows, classic);
+            self.render_view(vbuf, self.player_position(0), top)
+        } else {
+            let view = Viewport::new(2, 0, view_rows - 1, vbuf.cols);
+            self.render_player_status(vbuf, vbuf.rows - 1, classic);
+            self.render_view(vbuf, self.camera_position(), view)
+        };
+        if self.minimap {
+            self.render_minimap(vbuf);
+        }
         self.frames += 1;
 
         let seconds = self.start.elapsed().as_secs_f32();
         let fps =
             self.frames as f32 / if seconds == 0.0 { 1.0 } else { seconds };
-        let player_pos = self.player_position();
-        let player_dir = self.get_player().dir;
-        let stop_dir = self.get_player().stop_dir;
-        let firing_dir = self.firing_dir;
+        let player_pos = self.player_position(0);
+        let player_dir = self.get_player(0).dir;
+        let stop_dir = self.get_player(0).stop_dir;
+        let firing_dir = self.players[0].firing_dir;
         let maze_rows = self.maze.rows();
         let maze_cols = self.maze.cols();
         let entities = self.entities.len();
         let game_state = self.game_state;
-        let vbuf = &mut self.video.buffer;
+        let difficulty = self.levels.difficulty;
         if self.diagnostics {
             let mut players = 0;
             let mut brats = 0;
             let mut bullets = 0;
             let mut factories = 0;
             let mut rats = 0;
-            for entity in self.entities.iter() {
+            for (_, entity) in self.entities.iter() {
                 match entity {
                     Entity::Player(_) => players += 1,
                     Entity::Rat(_) => rats += 1,
@@ -89,8 +111,15 @@ impl GameContext {
             vbuf.print(14, 0, RD, format!("factories: {factories:4}"));
             vbuf.print(15, 0, RD, format!("  bullets: {bullets:4}"));
             vbuf.print(16, 0, RD, format!("superboom: {:4}", self.super_boom));
+            vbuf.print(17, 0, RD, format!("     seed: {}", self.seed));
+            vbuf.print(
+                18,
+                0,
+                RD,
+                format!("    level: {:4} ({})", self.level, difficulty.name()),
+            );
         }
-        let time = self.start.elapsed().as_secs();
+        let time = self.elapsed() / 1000;
 
         // rat stats
         vbuf.set_quad(0, 1, RATS_UP_A1, ATTR_NONE);
@@ -112,15 +141,24 @@ impl GameContext {
         vbuf.print(0, 49, ATTR_NONE, format!("Score: {:7}", self.score));
         vbuf.print(0, 66, ATTR_NONE, format!("Time:  {:4}", time));
         if classic {
-            vbuf.print(1, 49, ATTR_NONE, "High:        0");
-            vbuf.print(1, 66, ATTR_NONE, format!("Maze: {:5}", 32768));
-        } else {
             vbuf.print(
                 1,
                 49,
                 ATTR_NONE,
-                format!("Health:    {health:3}", health = self.health),
+                format!("High:  {:7}", high_scores.high_score()),
             );
+            vbuf.print(1, 66, ATTR_NONE, format!("Maze: {:5}", self.level));
+        } else {
+            // co-op games show each player's health on the status line
+            if self.players.len() == 1 {
+                vbuf.print(
+                    1,
+                    49,
+                    ATTR_NONE,
+                    format!("Health:    {:3}", self.players[0].health),
+                );
+            }
+            vbuf.print(1, 66, ATTR_NONE, format!("Level: {:4}", self.level));
         }
 
         if self.game_state == GameState::Paused {
@@ -129,28 +167,47 @@ impl GameContext {
             vbuf.print(row - 1, col, ATTR_REVERSE, "         ");
             vbuf.print(row, col, ATTR_REVERSE, "  PAUSE  ");
             vbuf.print(row + 1, col, ATTR_REVERSE, "         ");
-        } else if self.game_state == GameState::Finished {
+        } else if self.game_state == GameState::EnterInitials {
             let row = 2 + vbuf.rows / 2 - 5;
             let col = vbuf.cols / 2 - 10;
+            let initials =
+                format!("{:_<width$}", self.initials, width = INITIALS_LENGTH);
             vbuf.print(row + 1, col, ATTR_REVERSE, "                     ");
-            vbuf.print(row + 2, col, ATTR_REVERSE, "      GAME OVER      ");
+            vbuf.print(row + 2, col, ATTR_REVERSE, "   NEW HIGH SCORE!   ");
             vbuf.print(row + 3, col, ATTR_REVERSE, "                     ");
             vbuf.print(
                 row + 4,
                 col,
                 ATTR_REVERSE,
-                format!(
-                    " Final Score: {score:6} ",
-                    score = if self.score < self.time {
-                        0
-                    } else {
-                        self.score - self.time
-                    }
-                ),
+                format!(" Final Score: {:6} ", self.final_score()),
             );
             vbuf.print(row + 5, col, ATTR_REVERSE, "                     ");
-            vbuf.print(row + 6, col, ATTR_REVERSE, "   Play Again? Y/N   ");
+            vbuf.print(
+                row + 6,
+                col,
+                ATTR_REVERSE,
+                format!("   Initials: {initials}     "),
+            );
             vbuf.print(row + 7, col, ATTR_REVERSE, "                     ");
+        } else if self.game_state == GameState::Finished {
+            // game over box followed by the game statistics and the high
+            // score table
+            let row = 2 + (vbuf.rows - 2).saturating_sub(28) / 2;
+            let col = vbuf.cols / 2 - 10;
+            vbuf.print(row, col, ATTR_REVERSE, "                     ");
+            vbuf.print(row + 1, col, ATTR_REVERSE, "      GAME OVER      ");
+            vbuf.print(row + 2, col, ATTR_REVERSE, "                     ");
+            vbuf.print(
+                row + 3,
+                col,
+                ATTR_REVERSE,
+                format!(" Final Score: {:6} ", self.final_score()),
+            );
+            vbuf.print(row + 4, col, ATTR_REVERSE, "                     ");
+            vbuf.print(row + 5, col, ATTR_REVERSE, "   Play Again? Y/N   ");
+            vbuf.print(row + 6, col, ATTR_REVERSE, "                     ");
+            let rows = self.render_statistics(vbuf, row + 8);
+            render_high_scores(vbuf, row + 9 + rows, high_scores);
         }
 
         // if any factory is exploding light up the screen
@@ -164,9 +221,181 @@ impl GameContext {
             }
             self.super_boom -= 1;
         }
+    }
+
+    // copy the part of the maze centered on a position into a viewport and
+    // return the top left corner of that part, in a bounded maze the view
+    // stops at the edges of the maze (and a maze smaller than the view is
+    // centered in it)
+    fn render_view(
+        &self,
+        vbuf: &mut Buffer,
+        center: Position,
+        mut view: Viewport,
+    ) -> Position {
+        let mut start_pos = center;
+        start_pos.move_up(view.rows / 2);
+        start_pos.move_left(view.cols / 2);
+        if self.maze.is_bounded() {
+            let (row, rows) =
+                clamp_view(center.row, view.rows, self.maze.rows());
+            let (col, cols) =
+                clamp_view(center.col, view.cols, self.maze.cols());
+            start_pos = Position { row, col };
+            view = Viewport::new(
+                view.row + (view.rows - rows) / 2,
+                view.col + (view.cols - cols) / 2,
+                rows,
+                cols,
+            );
+        }
+        self.maze.buffer.copy_viewport(
+            start_pos.row,
+            start_pos.col,
+            vbuf,
+            view,
+            None,
+        );
+        start_pos
+    }
 
-        // blast the video buffer onto the screen
-        self.video.render_buffer(textures)
+    // halfway between the players, going the short way around the maze
+    fn camera_position(&self) -> Position {
+        let first = self.player_position(0);
+        let second = self.player_position(self.players.len() - 1);
+        if self.maze.is_bounded() {
+            return Position {
+                row: (first.row + second.row) / 2,
+                col: (first.col + second.col) / 2,
+            };
+        }
+        let halfway = |a: Pos, b: Pos, size: Size| {
+            let size = size as i32;
+            let mut delta = (b as i32 - a as i32).rem_euclid(size);
+            if delta > size / 2 {
+                delta -= size;
+            }
+            (a as i32 + delta / 2).rem_euclid(size) as Pos
+        };
+        Position {
+            row: halfway(first.row, second.row, self.maze.rows()),
+            col: halfway(first.col, second.col, self.maze.cols()),
+        }
+    }
+
+    // score (and health) of each player in a co-op game
+    fn render_player_status(&self, vbuf: &mut Buffer, row: Pos, classic: bool) {
+        let status: Vec<String> = self
+            .players
+            .iter()
+            .enumerate()
+            .map(|(index, player)| {
+                if classic {
+                    format!(" Player {}  Score: {:7} ", index + 1, player.score)
+                } else {
+                    format!(
+                        " Player {}  Score: {:7}  Health: {:3} ",
+                        index + 1,
+                        player.score,
+                        player.health
+                    )
+                }
+            })
+            .collect();
+        let width = vbuf.cols as usize;
+        let right = &status[status.len() - 1];
+        vbuf.print(
+            row,
+            0,
+            ATTR_REVERSE,
+            format!(
+                "{:<left$}{right}",
+                status[0],
+                left = width.saturating_sub(right.len())
+            ),
+        );
+    }
+
+    // returns the number of rows used
+    fn render_statistics(&self, vbuf: &mut Buffer, row: Pos) -> Pos {
+        let accuracy = match self.accuracy() {
+            Some(accuracy) => format!("{accuracy}%"),
+            None => "-".to_string(),
+        };
+        let mut stats = vec![
+            ("Rats killed:", self.dead_rats.to_string()),
+            ("Shots fired:", self.shots_fired.to_string()),
+            ("Brats killed:", self.dead_brats.to_string()),
+            ("Shots hit:", self.shots_hit.to_string()),
+            ("Factories killed:", self.dead_factories.to_string()),
+            ("Accuracy:", accuracy),
+            ("Players lost:", self.players_dead.to_string()),
+            ("Rat damage:", self.damage_from_rats.to_string()),
+            ("Level reached:", self.level.to_string()),
+            ("Brat damage:", self.damage_from_brats.to_string()),
+            ("Game time:", format!("{}s", self.time)),
+            ("Longest life:", format!("{}s", self.longest_life() / 1000)),
+        ];
+        if self.players.len() > 1 {
+            stats.push(("Player 1 score:", self.players[0].score.to_string()));
+            stats.push(("Player 2 score:", self.players[1].score.to_string()));
+        }
+        let width = HIGH_SCORES_HEADER.len();
+        let col = vbuf.cols.saturating_sub(width as Size) / 2;
+        vbuf.print(row, col, ATTR_REVERSE, format!("{:^width$}", "STATISTICS"));
+        for (index, pair) in stats.chunks(2).enumerate() {
+            let (left, left_value) = &pair[0];
+            let (right, right_value) = &pair[1];
+            vbuf.print(
+                row + 1 + index as Pos,
+                col,
+                ATTR_REVERSE,
+                format!(
+                    " {left:<17}{left_value:>7}   {right:<17}{right_value:>7}  "
+                ),
+            );
+        }
+        1 + (stats.len() / 2) as Pos
+    }
+}
+
+// first row (or column) of a bounded maze to show in a view centered on a
+// position and how many of them fit
+fn clamp_view(center: Pos, view: Size, size: Size) -> (Pos, Size) {
+    if size <= view {
+        (0, size)
+    } else {
+        (center.saturating_sub(view / 2).min(size - view), view)
+    }
+}
+
+const HIGH_SCORES_HEADER: &str =
+    "  # INI   SCORE  DATE           MAZE DENS FACT  TIME  ";
+
+fn render_high_scores(vbuf: &mut Buffer, row: Pos, high_scores: &HighScores) {
+    let width = HIGH_SCORES_HEADER.len();
+    let col = vbuf.cols.saturating_sub(width as Size) / 2;
+    vbuf.print(row, col, ATTR_REVERSE, format!("{:^width$}", "HIGH SCORES"));
+    vbuf.print(row + 1, col, ATTR_REVERSE, HIGH_SCORES_HEADER);
+    for (index, score) in high_scores.scores.iter().enumerate() {
+        vbuf.print(
+            row + 2 + index as Pos,
+            col,
+            ATTR_REVERSE,
+            format!(
+                " {:>2} {:3} {:>7}  {}  {:>7} {:>4} {:>4} {:>5}{} ",
+                index + 1,
+                score.initials,
+                score.score,
+                score.date_string(),
+                format!("{}x{}", score.maze_height, score.maze_width),
+                score.density,
+                score.factories,
+                score.time,
+                // mark games played in classic mode
+                if score.classic { "c" } else { " " }
+            ),
+        );
     }
 }
 
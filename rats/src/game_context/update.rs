@@ -1,69 +1,162 @@
-use super::GameState;
+use super::{random, GameState};
 use crate::{
-    config::{
-        BRAT_KILL, BULLET_HARMLESS_LIFETIME, FACTORY_KILL,
-        PLAYER_BLAST_RADIUS_SQUARED, RAT_KILL, SUPER_BOOM_FRAMES,
-    },
+    config::config,
     entities::{
-        update_brat, update_bullet, update_factory, update_player, update_rat,
-        Entity, EntityAction, State,
+        dir, update_brat, update_bullet, update_factory, update_player,
+        update_rat, Entity, EntityAction, EntityId, Player, Position, State,
     },
     game_context::GameContext,
+    maze::PRISTINE_MAZE,
 };
+use std::cmp::{max, min};
+use video::Sound;
 
 pub enum Action {
     Nothing,
     Delete,
     Update(Entity),
     New(Entity),
-    Attack(usize),
+    // (player, damage)
+    Attack(usize, usize),
 }
 
 impl GameContext {
+    // advance the simulation by one frame: update all entities, keep firing,
+    // spawn new rats and brats and check whether the level or game is over
+    //
+    // ticks only counts the steps taken while the game is running so that
+    // input replays aren't affected by how long the game was paused
+    pub fn step(&mut self, classic: bool) {
+        if self.game_state == GameState::Running {
+            self.ticks += 1;
+        }
+        self.clock.advance(self.game_state == GameState::Running);
+        let now = self.elapsed();
+        let config = config();
+        self.update();
+        for player in 0..self.players.len() {
+            if self.players[player].firing_dir != dir::NONE
+                && now >= self.players[player].next_fire_time
+            {
+                self.fire(player);
+            }
+        }
+        if now >= self.next_rat_spawn {
+            self.new_rats =
+                (self.live_factories as f32 * config.rats_per_factory) as usize;
+            self.next_rat_spawn = now + config.rat_spawn_seconds * 1000;
+        }
+        if self.live_rats > 0 && now >= self.next_brat_spawn {
+            // Breed you little bastards!
+            self.new_brats = self.live_rats / 8 + random(&mut self.rng, 2, 10);
+            self.next_brat_spawn = now + config.brat_spawn_seconds * 1000;
+        }
+        if self.game_state == GameState::Running
+            && ((self.live_factories == 0 && self.live_rats == 0)
+                || self.players_left == 0)
+        {
+            if !classic {
+                self.score += self.live_brats * config.brat_kill;
+                self.dead_brats += self.live_brats;
+                self.live_brats = 0;
+            }
+            if self.live_brats == 0 {
+                if self.players_left == 0 {
+                    self.time = (now / 1000) as usize;
+                    self.game_state = GameState::Finished;
+                } else {
+                    self.start_level(self.level + 1);
+                }
+            }
+        }
+    }
+
     pub fn update(&mut self) {
         if self.game_state == GameState::Running {
             let actions = self.update_actions();
             self.apply_actions(actions);
+            self.index_entities();
             self.bullet_hit_tests();
+            self.index_entities();
             self.player_update();
         }
     }
 
-    fn update_actions(&self) -> Vec<(usize, Action)> {
+    fn update_actions(&mut self) -> Vec<(EntityId, Action)> {
         let update = self.elapsed();
-        let mut actions: Vec<(usize, Action)> = vec![];
-        for (index, entity) in self.entities.iter().enumerate() {
+        let players: Vec<Player> = (0..self.players.len())
+            .map(|player| *self.get_player(player))
+            .collect();
+        // rats and brats hunt whichever live player is closest
+        let origins: Vec<Position> = players
+            .iter()
+            .filter(|player| player.state == State::Alive)
+            .map(|player| player.pos)
+            .collect();
+        let hunt_range = config().hunt_range;
+        PRISTINE_MAZE.with(|maze| {
+            self.flow_field.update(&maze.borrow(), &origins, hunt_range)
+        });
+        let nearest = |pos: Position| {
+            players
+                .iter()
+                .min_by_key(|player| {
+                    (
+                        player.state != State::Alive,
+                        pos.distance_squared_to(player.pos),
+                    )
+                })
+                .expect("there is always a player")
+        };
+        let field = &self.flow_field;
+        let spatial = &self.spatial;
+        let player_ids: Vec<EntityId> =
+            self.players.iter().map(|player| player.id).collect();
+        let attack = |pos, size, damage| {
+            player_ids
+                .iter()
+                .position(|&id| spatial.touches(pos, size, id))
+                .map(|player| (player, damage))
+        };
+        let rng = &mut self.rng;
+        let mut actions: Vec<(EntityId, Action)> = vec![];
+        for (id, entity) in self.entities.iter() {
             let action = match entity {
                 Entity::Player(player) => update_player(player, update),
                 Entity::Rat(rat) => update_rat(
                     rat,
-                    self.get_player(),
-                    self.rat_damage,
+                    nearest(rat.pos),
+                    attack(rat.pos, 2, self.rat_damage),
                     update,
                     self.new_brats != 0,
+                    field,
+                    rng,
                 ),
                 Entity::Brat(brat) => update_brat(
                     brat,
-                    self.get_player(),
-                    self.brat_damage,
+                    nearest(brat.pos),
+                    attack(brat.pos, 1, self.brat_damage),
                     update,
+                    field,
+                    rng,
                 ),
                 Entity::Factory(factory) => {
-                    update_factory(factory, update, self.new_rats != 0)
+                    update_factory(factory, update, self.new_rats != 0, rng)
                 }
                 Entity::Bullet(bullet) => update_bullet(bullet, update),
             };
-            actions.push((index, action));
+            actions.push((id, action));
         }
         actions
     }
 
-    fn apply_actions(&mut self, actions: Vec<(usize, Action)>) {
-        for (index, action) in actions.into_iter().rev() {
+    fn apply_actions(&mut self, actions: Vec<(EntityId, Action)>) {
+        let now = self.elapsed();
+        for (id, action) in actions {
             match action {
                 Action::Nothing => {}
                 Action::Delete => {
-                    match self.entities[index] {
+                    match self.entities[id] {
                         Entity::Player(_) => {}
                         Entity::Rat(_) => {
                             self.live_rats -= 1;
@@ -78,30 +171,25 @@ impl GameContext {
                             self.dead_factories += 1;
                         }
                         Entity::Bullet(_) => {
-                            self.video.play_impact();
+                            self.sounds.push(Sound::Impact);
                         }
                     }
-                    let last = self.entities.len() - 1;
-                    self.entities.swap(index, last);
-                    self.entities.truncate(last);
+                    self.entities.remove(id);
                 }
                 Action::Update(entity) => {
-                    if index == 0 {
-                        match (&self.entities[0], &entity) {
-                            (
-                                Entity::Player(current),
-                                Entity::Player(update),
-                            ) => {
-                                if current.state == State::Dead
-                                    && update.state == State::Alive
-                                {
-                                    self.health = 100;
-                                }
+                    if let (Entity::Player(current), Entity::Player(update)) =
+                        (&self.entities[id], &entity)
+                    {
+                        if current.state == State::Dead
+                            && update.state == State::Alive
+                        {
+                            if let Some(player) = self.player_index(id) {
+                                self.players[player].health = 100;
+                                self.players[player].life_start = now;
                             }
-                            _ => {}
                         }
                     }
-                    self.entities[index] = entity;
+                    self.entities[id] = entity;
                 }
                 Action::New(entity) => {
                     match entity {
@@ -121,21 +209,31 @@ impl GameContext {
                         Entity::Factory(_) => self.live_factories += 1,
                         Entity::Bullet(_) => {}
                     }
-                    self.entities.push(entity);
+                    self.entities.insert(entity);
                 }
-                Action::Attack(damage) => {
-                    let player = self.get_player();
-                    if player.state == State::Alive {
-                        self.entities[index].explode();
-                        self.video.play_short_explosion();
-                        if damage >= self.health {
+                Action::Attack(player, damage) => {
+                    let health = self.players[player].health;
+                    if self.get_player(player).state == State::Alive {
+                        let taken = min(damage, health);
+                        if let Entity::Brat(_) = self.entities[id] {
+                            self.damage_from_brats += taken;
+                        } else {
+                            self.damage_from_rats += taken;
+                        }
+                        self.entities[id].explode();
+                        self.sounds.push(Sound::ShortExplosion);
+                        if damage >= health {
                             if self.players_left > 0 {
-                                self.entities[0].explode();
+                                self.longest_life = max(
+                                    self.longest_life,
+                                    now - self.players[player].life_start,
+                                );
+                                self.get_player_mut(player).explode();
                                 self.players_left -= 1;
                                 self.players_dead += 1;
                             }
                         } else {
-                            self.health -= damage;
+                            self.players[player].health -= damage;
                         }
                     }
                 }
@@ -144,95 +242,131 @@ impl GameContext {
     }
 
     fn bullet_hit_tests(&mut self) {
+        let now = self.elapsed();
+        let config = config();
         let live_bullets: Vec<_> = self
             .entities
             .iter()
-            .enumerate()
-            .filter_map(|(index, entity)| match entity {
+            .filter_map(|(id, entity)| match entity {
                 Entity::Bullet(bullet) if bullet.state == State::Alive => {
-                    Some((index, bullet.pos, bullet.lifetime))
+                    Some((id, bullet.pos, bullet.lifetime, bullet.player))
                 }
                 _ => None,
             })
             .collect();
-        let mut marks = vec![false; self.entities.len()];
-        for (bullet_index, pos, lifetime) in live_bullets.into_iter().rev() {
-            for (entity_index, entity) in self.entities.iter_mut().enumerate() {
-                if entity.hit(pos) && bullet_index != entity_index {
-                    match entity {
+        let mut spent = vec![];
+        for (bullet_id, pos, lifetime, shooter) in live_bullets {
+            let targets: Vec<EntityId> =
+                self.spatial.at(pos).filter(|&id| id != bullet_id).collect();
+            // a bullet counts as one hit however many rats it takes out
+            let mut hit = false;
+            let mut used = false;
+            for target in targets {
+                let target_player = self.player_index(target);
+                let entity = &mut self.entities[target];
+                if entity.hit(pos) {
+                    let points = match entity {
                         Entity::Player(player) => {
-                            if lifetime > BULLET_HARMLESS_LIFETIME {
-                                self.super_boom = SUPER_BOOM_FRAMES;
+                            if lifetime > config.bullet_harmless_lifetime
+                                && self.players_left > 0
+                            {
+                                let life_start = target_player
+                                    .map_or(now, |index| {
+                                        self.players[index].life_start
+                                    });
+                                self.super_boom = config.super_boom_frames;
+                                self.longest_life =
+                                    max(self.longest_life, now - life_start);
                                 self.players_dead += 1;
                                 self.players_left -= 1;
                                 player.explode();
-                                self.video.play_short_explosion();
+                                self.sounds.push(Sound::ShortExplosion);
                             }
+                            0
                         }
                         Entity::Rat(rat) => {
-                            self.score += RAT_KILL;
+                            hit = true;
                             rat.explode();
-                            self.video.play_short_explosion();
+                            self.sounds.push(Sound::ShortExplosion);
+                            config.rat_kill
                         }
                         Entity::Brat(brat) => {
-                            self.score += BRAT_KILL;
+                            hit = true;
                             brat.explode();
-                            self.video.play_short_explosion();
+                            self.sounds.push(Sound::ShortExplosion);
+                            config.brat_kill
                         }
                         Entity::Factory(factory) => {
-                            self.super_boom = SUPER_BOOM_FRAMES;
-                            self.score += FACTORY_KILL;
+                            self.super_boom = config.super_boom_frames;
+                            hit = true;
                             factory.explode();
-                            self.video.play_long_explosion();
+                            self.sounds.push(Sound::LongExplosion);
+                            config.factory_kill
                         }
                         Entity::Bullet(bullet) => {
                             bullet.explode();
-                            self.video.play_impact();
+                            self.sounds.push(Sound::Impact);
+                            0
                         }
-                    }
-                    marks[bullet_index] = true;
+                    };
+                    self.score += points;
+                    self.players[shooter].score += points;
+                    used = true;
                 }
             }
-        }
-        for (index, marked) in marks.into_iter().enumerate().rev() {
-            if marked {
-                let last = self.entities.len() - 1;
-                if index != last {
-                    self.entities.swap(index, last);
-                }
-                self.entities.truncate(last);
+            if hit {
+                self.shots_hit += 1;
             }
+            if used {
+                spent.push(bullet_id);
+            }
+        }
+        for id in spent {
+            self.entities.remove(id);
         }
     }
 
+    // the spatial index has to be rebuilt whenever entities move
+    pub fn index_entities(&mut self) {
+        let (rows, cols) = (self.maze.rows(), self.maze.cols());
+        self.spatial.rebuild(rows, cols, &self.entities);
+    }
+
     // while a player is exploding, anything dangerous within its blast radius
     // also explodes (without scoring any points) in order to make it possible
     // for the player to recover
     fn player_update(&mut self) {
-        let player = self.get_player().clone();
+        for index in 0..self.players.len() {
+            self.blast_radius_update(index);
+        }
+    }
+
+    fn blast_radius_update(&mut self, index: usize) {
+        let player = *self.get_player(index);
+        let config = config();
+        let blast_radius_squared = config.player_blast_radius_squared();
         if player.state != State::Alive {
-            for entity in self.entities.iter_mut() {
-                match entity {
-                    Entity::Rat(rat) => {
+            let near =
+                self.spatial.near(player.pos, config.player_blast_radius);
+            for id in near {
+                match &mut self.entities[id] {
+                    Entity::Rat(rat)
                         if rat.pos.distance_squared_to(player.pos)
-                            < PLAYER_BLAST_RADIUS_SQUARED
-                        {
-                            rat.explode();
-                        }
+                            < blast_radius_squared =>
+                    {
+                        rat.explode();
                     }
-                    Entity::Brat(brat) => {
+                    Entity::Brat(brat)
                         if brat.pos.distance_squared_to(player.pos)
-                            < PLAYER_BLAST_RADIUS_SQUARED
-                        {
-                            brat.explode();
-                        }
+                            < blast_radius_squared =>
+                    {
+                        brat.explode();
                     }
-                    Entity::Bullet(bullet) => {
+                    Entity::Bullet(bullet)
                         if bullet.pos.distance_squared_to(player.pos)
-                            < PLAYER_BLAST_RADIUS_SQUARED
-                        {
-                            bullet.explode();
-                        }
+                            < blast_radius_squared =>
+                    {
+                        bullet.explode();
                     }
                     _ => {}
                 }
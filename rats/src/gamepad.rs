@@ -0,0 +1,193 @@
+use crate::{
+    entities::{dir, Direction},
+    game_context::MAX_PLAYERS,
+    input::{Command, DIRECTIONS},
+};
+use video::{Axis, Button, GameController, Video};
+
+// Game controllers stand in for the keyboard: they turn stick and button
+// events into the same commands as keys, so that they work the same way (and
+// are recorded in replays) without any special handling. The first controller
+// belongs to player 1 and the second one to player 2.
+//
+// The left stick or d-pad moves, the right stick fires (both in 8 directions),
+// start pauses, back quits and A/B answer the play again question.
+
+// how far (out of 32767) a stick has to be pushed in a direction
+const DEAD_ZONE: i16 = 16_000;
+
+// command (down or up) to pass on
+pub type CommandEvent = (bool, Command);
+
+struct Gamepad {
+    controller: GameController,
+    player: usize,
+    left_stick: Direction,
+    dpad: Direction,
+    right_stick: Direction,
+}
+
+impl Gamepad {
+    fn movement(&self) -> Direction {
+        self.left_stick | self.dpad
+    }
+}
+
+#[derive(Default)]
+pub struct Gamepads {
+    gamepads: Vec<Gamepad>,
+}
+
+impl Gamepads {
+    pub fn new() -> Gamepads {
+        Gamepads::default()
+    }
+
+    // a controller was plugged in (or was already there at startup)
+    pub fn add(&mut self, video: &Video, joystick_index: u32) {
+        let player = (0..MAX_PLAYERS).find(|player| {
+            self.gamepads
+                .iter()
+                .all(|gamepad| gamepad.player != *player)
+        });
+        if let Some(player) = player {
+            match video.open_controller(joystick_index) {
+                Ok(controller) => self.gamepads.push(Gamepad {
+                    controller,
+                    player,
+                    left_stick: dir::NONE,
+                    dpad: dir::NONE,
+                    right_stick: dir::NONE,
+                }),
+                Err(error) => println!("game controller error: {error}"),
+            }
+        }
+    }
+
+    // a controller was unplugged, let go of everything it was holding down
+    pub fn remove(&mut self, which: u32) -> Vec<CommandEvent> {
+        let mut events = vec![];
+        if let Some(index) = self.find(which) {
+            let gamepad = self.gamepads.remove(index);
+            let player = gamepad.player;
+            let (movement, firing) = (gamepad.movement(), gamepad.right_stick);
+            changes(&mut events, Command::Move, player, movement, dir::NONE);
+            changes(&mut events, Command::Fire, player, firing, dir::NONE);
+        }
+        events
+    }
+
+    pub fn axis(
+        &mut self,
+        which: u32,
+        axis: Axis,
+        value: i16,
+    ) -> Vec<CommandEvent> {
+        let mut events = vec![];
+        let Some(index) = self.find(which) else {
+            return events;
+        };
+        let gamepad = &mut self.gamepads[index];
+        let player = gamepad.player;
+        match axis {
+            Axis::LeftX | Axis::LeftY => {
+                let before = gamepad.movement();
+                gamepad.left_stick = stick(gamepad.left_stick, axis, value);
+                let after = gamepad.movement();
+                changes(&mut events, Command::Move, player, before, after);
+            }
+            Axis::RightX | Axis::RightY => {
+                let before = gamepad.right_stick;
+                gamepad.right_stick = stick(gamepad.right_stick, axis, value);
+                let after = gamepad.right_stick;
+                changes(&mut events, Command::Fire, player, before, after);
+            }
+            _ => {}
+        }
+        events
+    }
+
+    pub fn button(
+        &mut self,
+        which: u32,
+        button: Button,
+        down: bool,
+    ) -> Vec<CommandEvent> {
+        let mut events = vec![];
+        let Some(index) = self.find(which) else {
+            return events;
+        };
+        let gamepad = &mut self.gamepads[index];
+        let pad_dir = match button {
+            Button::DPadUp => dir::UP,
+            Button::DPadDown => dir::DOWN,
+            Button::DPadLeft => dir::LEFT,
+            Button::DPadRight => dir::RIGHT,
+            _ => dir::NONE,
+        };
+        if pad_dir != dir::NONE {
+            let before = gamepad.movement();
+            if down {
+                gamepad.dpad |= pad_dir;
+            } else {
+                gamepad.dpad &= !pad_dir;
+            }
+            let after = gamepad.movement();
+            changes(&mut events, Command::Move, gamepad.player, before, after);
+        } else {
+            let command = match button {
+                Button::Start => Some(Command::Pause),
+                Button::Back => Some(Command::Quit),
+                Button::A => Some(Command::PlayAgain),
+                Button::B => Some(Command::DontPlayAgain),
+                _ => None,
+            };
+            if let Some(command) = command {
+                events.push((down, command));
+            }
+        }
+        events
+    }
+
+    fn find(&self, which: u32) -> Option<usize> {
+        self.gamepads
+            .iter()
+            .position(|gamepad| gamepad.controller.instance_id() == which)
+    }
+}
+
+// new stick direction after it moved along one axis
+fn stick(current: Direction, axis: Axis, value: i16) -> Direction {
+    let (negative, positive) = match axis {
+        Axis::LeftX | Axis::RightX => (dir::LEFT, dir::RIGHT),
+        _ => (dir::UP, dir::DOWN),
+    };
+    let current = current & !(negative | positive);
+    if value <= -DEAD_ZONE {
+        current | negative
+    } else if value >= DEAD_ZONE {
+        current | positive
+    } else {
+        current
+    }
+}
+
+// ups for directions that are no longer held and downs for new ones
+fn changes(
+    events: &mut Vec<CommandEvent>,
+    command: fn(usize, Direction) -> Command,
+    player: usize,
+    before: Direction,
+    after: Direction,
+) {
+    for dir in DIRECTIONS {
+        if before & dir != 0 && after & dir == 0 {
+            events.push((false, command(player, dir)));
+        }
+    }
+    for dir in DIRECTIONS {
+        if before & dir == 0 && after & dir != 0 {
+            events.push((true, command(player, dir)));
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use crate::errors::Result;
+use std::{
+    cmp::Reverse,
+    env,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use video::Size;
+
+pub const HIGH_SCORE_ENTRIES: usize = 10;
+pub const INITIALS_LENGTH: usize = 3;
+
+const HIGH_SCORES_HEADER: &str = "rats high scores 1";
+
+#[derive(Debug, Clone)]
+pub struct HighScore {
+    pub initials: String,
+    pub score: usize,
+    // seconds since the unix epoch
+    pub date: u64,
+    pub maze_height: Size,
+    pub maze_width: Size,
+    pub density: usize,
+    pub factories: usize,
+    pub classic: bool,
+    // game time in seconds
+    pub time: usize,
+}
+
+impl HighScore {
+    // date the score was set as YYYY-MM-DD
+    pub fn date_string(&self) -> String {
+        let (year, month, day) = civil_from_days((self.date / 86_400) as i64);
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+}
+
+// top scores kept in a tab separated text file, best score first
+pub struct HighScores {
+    path: PathBuf,
+    pub scores: Vec<HighScore>,
+}
+
+impl HighScores {
+    // a missing file just means that nobody has played yet, lines that can't
+    // be parsed are skipped
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<HighScores> {
+        let mut high_scores = HighScores {
+            path: path.as_ref().to_path_buf(),
+            scores: vec![],
+        };
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                return Ok(high_scores)
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let mut lines = BufReader::new(file).lines();
+        match lines.next() {
+            Some(Ok(line)) if line == HIGH_SCORES_HEADER => {}
+            _ => return Ok(high_scores),
+        }
+        for line in lines {
+            if let Some(score) = parse_high_score(&line?) {
+                high_scores.scores.push(score);
+            }
+        }
+        high_scores.scores.sort_by_key(|score| Reverse(score.score));
+        high_scores.scores.truncate(HIGH_SCORE_ENTRIES);
+        Ok(high_scores)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let mut file = BufWriter::new(File::create(&self.path)?);
+        writeln!(file, "{HIGH_SCORES_HEADER}")?;
+        for score in self.scores.iter() {
+            writeln!(
+                file,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                score.initials,
+                score.score,
+                score.date,
+                score.maze_height,
+                score.maze_width,
+                score.density,
+                score.factories,
+                score.classic,
+                score.time
+            )?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    pub fn high_score(&self) -> usize {
+        self.scores.first().map(|score| score.score).unwrap_or(0)
+    }
+
+    pub fn qualifies(&self, score: usize) -> bool {
+        score > 0
+            && (self.scores.len() < HIGH_SCORE_ENTRIES
+                || self.scores.iter().any(|entry| score > entry.score))
+    }
+
+    // add a new score to the table, ties go to the older score
+    pub fn insert(&mut self, score: HighScore) {
+        let index = self
+            .scores
+            .iter()
+            .position(|entry| score.score > entry.score)
+            .unwrap_or(self.scores.len());
+        self.scores.insert(index, score);
+        self.scores.truncate(HIGH_SCORE_ENTRIES);
+    }
+}
+
+// ~/.rats_high_scores (or the current directory if there is no home)
+pub fn default_high_scores_path() -> PathBuf {
+    env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(".rats_high_scores")
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn parse_high_score(line: &str) -> Option<HighScore> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    match fields[..] {
+        [initials, score, date, maze_height, maze_width, density, factories, classic, time] => {
+            Some(HighScore {
+                initials: initials.chars().take(INITIALS_LENGTH).collect(),
+                score: score.parse().ok()?,
+                date: date.parse().ok()?,
+                maze_height: maze_height.parse().ok()?,
+                maze_width: maze_width.parse().ok()?,
+                density: density.parse().ok()?,
+                factories: factories.parse().ok()?,
+                classic: classic.parse().ok()?,
+                time: time.parse().ok()?,
+            })
+        }
+        _ => None,
+    }
+}
+
+// convert days since 1970-01-01 into a (year, month, day) date
+// see: http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
@@ -0,0 +1,396 @@
+use crate::{
+    entities::{dir, Direction},
+    errors::{config_error, Result},
+    game_context::MAX_PLAYERS,
+};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+use video::{Buffer, Keycode, Pos, Size, ATTR_NONE, ATTR_REVERSE};
+
+// Everything a player can do with a key (or a game controller). Keys are
+// mapped to commands through a KeyMap, which can be changed in a key map file
+// or on the in-game settings screen, and commands are what get recorded in
+// replays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Move(usize, Direction),
+    Fire(usize, Direction),
+    Pause,
+    Quit,
+    Diagnostics,
+    Minimap,
+    PlayAgain,
+    DontPlayAgain,
+    Settings,
+    Fullscreen,
+    Screenshot,
+    RecordGif,
+    SaveMaze,
+}
+
+// up, down, left, right
+pub const DIRECTIONS: [Direction; 4] =
+    [dir::UP, dir::DOWN, dir::LEFT, dir::RIGHT];
+
+impl Command {
+    // every command in the order they are listed on the settings screen
+    pub fn all() -> Vec<Command> {
+        let mut commands = vec![];
+        for player in 0..MAX_PLAYERS {
+            for dir in DIRECTIONS {
+                commands.push(Command::Move(player, dir));
+            }
+            for dir in DIRECTIONS {
+                commands.push(Command::Fire(player, dir));
+            }
+        }
+        commands.extend([
+            Command::Pause,
+            Command::Quit,
+            Command::Diagnostics,
+            Command::Minimap,
+            Command::PlayAgain,
+            Command::DontPlayAgain,
+            Command::Settings,
+            Command::Fullscreen,
+            Command::Screenshot,
+            Command::RecordGif,
+            Command::SaveMaze,
+        ]);
+        commands
+    }
+
+    // name used in key map and replay files, e.g. move_up or p2_fire_left
+    pub fn name(&self) -> String {
+        let player = |player: usize| match player {
+            0 => String::new(),
+            _ => format!("p{}_", player + 1),
+        };
+        match *self {
+            Command::Move(number, dir) => {
+                format!("{}move_{}", player(number), dir_name(dir))
+            }
+            Command::Fire(number, dir) => {
+                format!("{}fire_{}", player(number), dir_name(dir))
+            }
+            Command::Pause => "pause".to_string(),
+            Command::Quit => "quit".to_string(),
+            Command::Diagnostics => "diagnostics".to_string(),
+            Command::Minimap => "minimap".to_string(),
+            Command::PlayAgain => "play_again".to_string(),
+            Command::DontPlayAgain => "dont_play_again".to_string(),
+            Command::Settings => "settings".to_string(),
+            Command::Fullscreen => "fullscreen".to_string(),
+            Command::Screenshot => "screenshot".to_string(),
+            Command::RecordGif => "record_gif".to_string(),
+            Command::SaveMaze => "save_maze".to_string(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Command> {
+        Command::all()
+            .into_iter()
+            .find(|command| command.name() == name)
+    }
+
+    // what the settings screen calls it
+    pub fn description(&self) -> String {
+        match *self {
+            Command::Move(player, dir) => {
+                format!("Player {} move {}", player + 1, dir_name(dir))
+            }
+            Command::Fire(player, dir) => {
+                format!("Player {} fire {}", player + 1, dir_name(dir))
+            }
+            Command::Pause => "Pause".to_string(),
+            Command::Quit => "Quit".to_string(),
+            Command::Diagnostics => "Diagnostics".to_string(),
+            Command::Minimap => "Minimap".to_string(),
+            Command::PlayAgain => "Play again".to_string(),
+            Command::DontPlayAgain => "Don't play again".to_string(),
+            Command::Settings => "Settings".to_string(),
+            Command::Fullscreen => "Fullscreen".to_string(),
+            Command::Screenshot => "Screenshot".to_string(),
+            Command::RecordGif => "Record GIF".to_string(),
+            Command::SaveMaze => "Save maze".to_string(),
+        }
+    }
+}
+
+fn dir_name(dir: Direction) -> &'static str {
+    match dir {
+        dir::UP => "up",
+        dir::DOWN => "down",
+        dir::LEFT => "left",
+        _ => "right",
+    }
+}
+
+// A key map file lists the key for each command that isn't bound to its
+// default key, using SDL key names:
+//
+//   move_up = "I"
+//   move_left = "J"
+//   fire_up = "Keypad 8"
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    path: Option<PathBuf>,
+    bindings: Vec<(Command, Keycode)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> KeyMap {
+        use Keycode::*;
+        let keys = [
+            [Up, Down, Left, Right],
+            [W, S, A, D],
+            [T, G, F, H],
+            [I, K, J, L],
+        ];
+        let mut bindings = vec![];
+        for player in 0..MAX_PLAYERS {
+            for (dir, key) in DIRECTIONS.into_iter().zip(keys[player * 2]) {
+                bindings.push((Command::Move(player, dir), key));
+            }
+            for (dir, key) in DIRECTIONS.into_iter().zip(keys[player * 2 + 1]) {
+                bindings.push((Command::Fire(player, dir), key));
+            }
+        }
+        bindings.extend([
+            (Command::Pause, Space),
+            (Command::Quit, Escape),
+            (Command::Diagnostics, F12),
+            (Command::Minimap, M),
+            (Command::PlayAgain, Y),
+            (Command::DontPlayAgain, N),
+            (Command::Settings, F1),
+            (Command::Fullscreen, F11),
+            (Command::Screenshot, F9),
+            (Command::RecordGif, F10),
+            (Command::SaveMaze, F8),
+        ]);
+        KeyMap {
+            path: None,
+            bindings,
+        }
+    }
+}
+
+impl KeyMap {
+    // default key bindings changed by the ones in the file (if it exists),
+    // changes made on the settings screen are saved to the same file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<KeyMap> {
+        let path = path.as_ref();
+        let mut keymap = KeyMap {
+            path: Some(path.to_path_buf()),
+            ..KeyMap::default()
+        };
+        if !path.exists() {
+            return Ok(keymap);
+        }
+        let error = |message: String| {
+            config_error(format!("{}: {}", path.display(), message))
+        };
+        let keys: BTreeMap<String, String> = toml::from_str(
+            &fs::read_to_string(path)?,
+        )
+        .map_err(|toml_error| error(toml_error.message().to_string()))?;
+        for (name, key) in keys {
+            let command = Command::from_name(&name)
+                .ok_or_else(|| error(format!("unknown command: {name}")))?;
+            let keycode = Keycode::from_name(&key)
+                .ok_or_else(|| error(format!("unknown key: {key}")))?;
+            keymap.bind(command, keycode);
+        }
+        Ok(keymap)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let defaults = KeyMap::default();
+        let mut contents = String::new();
+        for &(command, keycode) in self.bindings.iter() {
+            if defaults.key(command) != keycode {
+                let name = toml::Value::String(keycode.name());
+                contents.push_str(&format!("{} = {name}\n", command.name()));
+            }
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    pub fn command(&self, keycode: Keycode) -> Option<Command> {
+        self.bindings
+            .iter()
+            .find(|(_, key)| *key == keycode)
+            .map(|(command, _)| *command)
+    }
+
+    pub fn key(&self, command: Command) -> Keycode {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == command)
+            .map(|(_, keycode)| *keycode)
+            .expect("every command has a key")
+    }
+
+    // a key can only be bound to one command, so whatever command had the
+    // key before gets this command's old key
+    pub fn bind(&mut self, command: Command, keycode: Keycode) {
+        let old_key = self.key(command);
+        for binding in self.bindings.iter_mut() {
+            if binding.0 == command {
+                binding.1 = keycode;
+            } else if binding.1 == keycode {
+                binding.1 = old_key;
+            }
+        }
+    }
+}
+
+// ~/.rats_keys.toml (or the current directory if there is no home)
+pub fn default_keys_path() -> PathBuf {
+    env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(".rats_keys.toml")
+}
+
+// The settings screen lists every command along with its key. The arrow keys
+// select a command, return waits for the new key for it and escape leaves the
+// screen. These keys always work the same way so that it's impossible to get
+// stuck with an unusable key map.
+#[derive(Debug, Default)]
+pub struct Settings {
+    selected: usize,
+    waiting: bool,
+}
+
+impl Settings {
+    pub fn new() -> Settings {
+        Settings::default()
+    }
+
+    // returns true when it's time to leave the settings screen
+    pub fn key_down(&mut self, keymap: &mut KeyMap, keycode: Keycode) -> bool {
+        let commands = Command::all();
+        if self.waiting {
+            self.waiting = false;
+            if keycode != Keycode::Escape {
+                keymap.bind(commands[self.selected], keycode);
+            }
+            return false;
+        }
+        match keycode {
+            Keycode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            Keycode::Down => {
+                self.selected = (self.selected + 1).min(commands.len() - 1);
+            }
+            Keycode::Return | Keycode::KpEnter => self.waiting = true,
+            Keycode::Escape => return true,
+            _ => {}
+        }
+        false
+    }
+
+    pub fn render(&self, vbuf: &mut Buffer, keymap: &KeyMap) {
+        let commands = Command::all();
+        let width = 40;
+        let col = vbuf.cols.saturating_sub(width as Size) / 2;
+        // keep the selected command visible on small screens
+        let visible = (vbuf.rows as usize).saturating_sub(8).max(1);
+        let first = self.selected.saturating_sub(visible - 1);
+        let mut row: Pos = 3;
+        vbuf.print(row, col, ATTR_REVERSE, format!("{:^width$}", "KEYS"));
+        row += 1;
+        vbuf.print(row, col, ATTR_REVERSE, format!("{:width$}", ""));
+        for (index, command) in
+            commands.iter().enumerate().skip(first).take(visible)
+        {
+            row += 1;
+            let key = if self.waiting && index == self.selected {
+                "press a key".to_string()
+            } else {
+                keymap.key(*command).name()
+            };
+            let line = format!(" {:<22}{:>16} ", command.description(), key);
+            let attr = if index == self.selected {
+                ATTR_NONE
+            } else {
+                ATTR_REVERSE
+            };
+            vbuf.print(row, col, attr, line);
+        }
+        row += 1;
+        vbuf.print(row, col, ATTR_REVERSE, format!("{:width$}", ""));
+        vbuf.print(
+            row + 1,
+            col,
+            ATTR_REVERSE,
+            format!("{:^width$}", "Return: change key  Esc: done"),
+        );
+        vbuf.print(row + 2, col, ATTR_REVERSE, format!("{:width$}", ""));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("rats-keys-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn binding_a_used_key_swaps_keys() {
+        let mut keymap = KeyMap::default();
+        keymap.bind(Command::Move(0, dir::UP), Keycode::Down);
+        assert_eq!(keymap.key(Command::Move(0, dir::UP)), Keycode::Down);
+        assert_eq!(keymap.key(Command::Move(0, dir::DOWN)), Keycode::Up);
+        assert_eq!(
+            keymap.command(Keycode::Up),
+            Some(Command::Move(0, dir::DOWN))
+        );
+        // an unused key doesn't take anything away from other commands
+        keymap.bind(Command::Pause, Keycode::P);
+        assert_eq!(keymap.key(Command::Pause), Keycode::P);
+        assert_eq!(keymap.command(Keycode::Space), None);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = temp_path("save_and_load");
+        let mut keymap = KeyMap::load(&path).unwrap();
+        keymap.bind(Command::Pause, Keycode::P);
+        keymap.bind(Command::Fire(1, dir::LEFT), Keycode::Kp4);
+        keymap.save().unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        let loaded = KeyMap::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // only the keys that changed are saved
+        assert_eq!(saved, "p2_fire_left = \"Keypad 4\"\npause = \"P\"\n");
+        for command in Command::all() {
+            assert_eq!(loaded.key(command), keymap.key(command));
+        }
+    }
+
+    #[test]
+    fn load_rejects_unknown_names() {
+        let path = temp_path("unknown_names");
+        fs::write(&path, "jump = \"J\"\n").unwrap();
+        let unknown_command = KeyMap::load(&path);
+        fs::write(&path, "pause = \"Nope\"\n").unwrap();
+        let unknown_key = KeyMap::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(unknown_command.is_err());
+        assert!(unknown_key.is_err());
+    }
+}
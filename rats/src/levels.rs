@@ -0,0 +1,235 @@
+use crate::{
+    config::Config,
+    maze::{MazeAlgorithm, MAZE_CELL_COLS, MAZE_CELL_ROWS},
+};
+use clap::ValueEnum;
+use std::cmp::{max, min};
+use video::{InitOptions, Size};
+
+// mazes stop growing once they get this large (in maze cells)
+pub const MAX_MAZE_CELLS: Size = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Classic,
+}
+
+impl Difficulty {
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Classic => "classic",
+        }
+    }
+
+    // how the game gets harder from one level to the next
+    fn curve(self) -> Curve {
+        match self {
+            Difficulty::Easy => Curve {
+                factories: 3,
+                more_factories: 1,
+                density: 60,
+                more_density: 5,
+                growth: 0,
+                speedup: 3,
+                faster_spawns: 3,
+                smarts: 50,
+                more_smarts: 2,
+            },
+            Difficulty::Normal => Curve {
+                factories: 5,
+                more_factories: 1,
+                density: 75,
+                more_density: 5,
+                growth: 1,
+                speedup: 5,
+                faster_spawns: 5,
+                smarts: 100,
+                more_smarts: 5,
+            },
+            Difficulty::Hard => Curve {
+                factories: 8,
+                more_factories: 2,
+                density: 85,
+                more_density: 5,
+                growth: 2,
+                speedup: 8,
+                faster_spawns: 8,
+                smarts: 150,
+                more_smarts: 5,
+            },
+            // a new maze of the same size every level, just more (and faster)
+            // rats that never go looking for the player
+            Difficulty::Classic => Curve {
+                factories: 5,
+                more_factories: 1,
+                density: 75,
+                more_density: 0,
+                growth: 0,
+                speedup: 5,
+                faster_spawns: 5,
+                smarts: 0,
+                more_smarts: 0,
+            },
+        }
+    }
+}
+
+struct Curve {
+    // rat factories on the first level and how many more on each level after
+    factories: usize,
+    more_factories: usize,
+    // maze density on the first level and how much denser each level gets
+    density: usize,
+    more_density: usize,
+    // maze cells added to the height and width of the maze each level
+    growth: Size,
+    // percentage by which rats and brats get faster each level
+    speedup: u32,
+    // percentage by which spawn intervals get shorter each level
+    faster_spawns: u32,
+    // rat and brat smarts on the first level (as a percentage of the
+    // configured values) and how much smarter they get each level
+    smarts: u32,
+    more_smarts: u32,
+}
+
+// everything that changes from one level to the next
+#[derive(Debug, Clone, Copy)]
+pub struct Level {
+    pub maze_height: Size,
+    pub maze_width: Size,
+    pub density: usize,
+    pub factories: usize,
+    pub algorithm: MazeAlgorithm,
+    pub bounded: bool,
+    pub config: Config,
+}
+
+pub struct Levels {
+    pub difficulty: Difficulty,
+    first: Level,
+}
+
+impl Levels {
+    // screen_rows and screen_cols are the size of the video buffer (if any),
+    // the maze is made at least large enough to fill the screen, settings
+    // from the command line take the place of the preset's first level
+    pub fn new(
+        difficulty: Difficulty,
+        algorithm: MazeAlgorithm,
+        bounded: bool,
+        opts: &InitOptions,
+        screen_rows: Size,
+        screen_cols: Size,
+        config: Config,
+    ) -> Levels {
+        let curve = difficulty.curve();
+        Levels {
+            difficulty,
+            first: Level {
+                maze_height: max(
+                    screen_rows.saturating_sub(2) / MAZE_CELL_ROWS,
+                    opts.maze_height.unwrap_or(15),
+                ),
+                maze_width: max(
+                    screen_cols / MAZE_CELL_COLS,
+                    opts.maze_width.unwrap_or(15),
+                ),
+                density: opts.density.unwrap_or(curve.density),
+                factories: opts.factories.unwrap_or(curve.factories),
+                algorithm,
+                bounded,
+                config,
+            },
+        }
+    }
+
+    // levels are numbered from 1
+    pub fn level(&self, number: usize) -> Level {
+        let curve = self.difficulty.curve();
+        let first = &self.first;
+        let n = number.saturating_sub(1);
+        let growth = min(n, MAX_MAZE_CELLS as usize) as Size * curve.growth;
+        let mut config = first.config;
+        config.rat_update_ms = faster(config.rat_update_ms, curve.speedup, n);
+        config.brat_update_ms = faster(config.brat_update_ms, curve.speedup, n);
+        config.rat_spawn_seconds =
+            faster(config.rat_spawn_seconds, curve.faster_spawns, n);
+        config.brat_spawn_seconds =
+            faster(config.brat_spawn_seconds, curve.faster_spawns, n);
+        config.rat_smarts = smarter(config.rat_smarts, &curve, n);
+        config.brat_smarts = smarter(config.brat_smarts, &curve, n);
+        Level {
+            maze_height: grow(first.maze_height, growth),
+            maze_width: grow(first.maze_width, growth),
+            density: min(first.density + n * curve.more_density, 100),
+            factories: (first.factories + n * curve.more_factories)
+                .clamp(1, 100),
+            algorithm: first.algorithm.for_level(number),
+            bounded: first.bounded,
+            config,
+        }
+    }
+}
+
+fn grow(cells: Size, growth: Size) -> Size {
+    if cells >= MAX_MAZE_CELLS {
+        cells
+    } else {
+        min(cells + growth, MAX_MAZE_CELLS)
+    }
+}
+
+// shorten a time by percent, levels times over, but never by more than two
+// thirds
+fn faster(time: u32, percent: u32, levels: usize) -> u32 {
+    let mut faster = time;
+    for _ in 0..levels {
+        faster -= faster * percent / 100;
+        if faster <= time / 3 {
+            return max(time / 3, 1);
+        }
+    }
+    faster
+}
+
+fn smarter(smarts: u32, curve: &Curve, levels: usize) -> u32 {
+    if curve.smarts == 0 {
+        return 0;
+    }
+    min(
+        smarts * curve.smarts / 100 + levels as u32 * curve.more_smarts,
+        100,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Maze;
+    use video::MAZE_CROSS;
+
+    #[test]
+    fn largest_maze_fits_in_a_buffer() {
+        let mut maze = Maze::new(MAX_MAZE_CELLS, MAX_MAZE_CELLS, false);
+        let (rows, cols) = (maze.rows(), maze.cols());
+        assert_eq!(rows, (MAZE_CELL_ROWS + 1) * MAX_MAZE_CELLS);
+        assert_eq!(cols, (MAZE_CELL_COLS + 1) * MAX_MAZE_CELLS);
+        // the last character doesn't wrap around onto an earlier one
+        maze.buffer.set_char(rows - 1, cols - 1, MAZE_CROSS);
+        assert!(maze.is_wall(rows - 1, cols - 1));
+        let walls = maze
+            .buffer
+            .characters
+            .iter()
+            .filter(|&&ch| ch == MAZE_CROSS);
+        assert_eq!(walls.count(), 1);
+    }
+}
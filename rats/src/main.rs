@@ -1,23 +1,45 @@
-use crate::game_context::random;
+use capture::Capture;
 use clap::Parser;
-use config::{
-    BRAT_KILL, BRAT_SPAWN_SECONDS, RATS_PER_FACTORY, RAT_SPAWN_SECONDS,
-};
-use entities::dir;
+use clock::{Clock, RealClock, SimulatedClock};
+use config::{config, default_config_path, set_config, Config};
+use editor::Editor;
+use errors::{config_error, Result};
 use game_context::{GameContext, GameState};
+use gamepad::Gamepads;
+use high_scores::{
+    default_high_scores_path, now, HighScore, HighScores, INITIALS_LENGTH,
+};
+use input::{default_keys_path, Command, KeyMap, Settings};
+use levels::{Difficulty, Levels};
+use maze::{MazeAlgorithm, MazeFile};
+use replay::Replay;
 use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
     thread::sleep,
     time::{Duration, Instant},
 };
 use video::{
-    sdl_error, Event, InitOptions, Keycode, PixelFormatEnum, Pixels, Result,
-    Size, ATTR_COMBOS, CHAR_CELL_HEIGHT, CHAR_CELL_WIDTH, FONT_SIZE,
+    sdl_error, Event, InitOptions, Keycode, PixelFormatEnum, Pixels, Size,
+    Texture, TextureCreator, Video, VideoError, WindowContext, WindowEvent,
+    ATTR_COMBOS, CHAR_CELL_HEIGHT, CHAR_CELL_WIDTH, FONT_SIZE,
 };
 
+mod capture;
+mod clock;
 mod config;
+mod editor;
 mod entities;
+mod errors;
 mod game_context;
+mod gamepad;
+mod high_scores;
+mod input;
+mod levels;
 mod maze;
+mod pathfinding;
+mod replay;
+mod spatial;
 
 #[derive(Parser)]
 struct CommandLineOpts {
@@ -45,6 +67,42 @@ struct CommandLineOpts {
     #[clap(short = 'm', long = "maze-density")]
     density: Option<usize>,
 
+    /// Maze generation algorithm (mixed uses a different one every level)
+    #[clap(long = "maze-algorithm", value_enum, default_value_t)]
+    algorithm: MazeAlgorithm,
+
+    /// Put a solid wall around the maze instead of wrapping around
+    #[clap(long = "bounded", action)]
+    bounded: bool,
+
+    /// Play every level in a maze from a maze file
+    #[clap(
+        long = "maze-file",
+        conflicts_with_all = ["maze_height", "maze_width", "density", "algorithm"]
+    )]
+    maze_file: Option<PathBuf>,
+
+    /// Edit a maze file (a new one is the size given by --maze-height and
+    /// --maze-width)
+    #[clap(
+        long = "edit",
+        value_name = "MAZE_FILE",
+        conflicts_with_all = ["maze_file", "record", "replay", "headless"]
+    )]
+    edit: Option<PathBuf>,
+
+    /// Two player co-op (player 2 moves with TFGH and fires with IJKL)
+    #[clap(long = "coop", action)]
+    coop: bool,
+
+    /// Give each player half of the screen in co-op mode
+    #[clap(long = "split-screen", action, requires = "coop")]
+    split_screen: bool,
+
+    /// Difficulty (default: normal, or classic in classic mode)
+    #[clap(long = "difficulty", value_enum)]
+    difficulty: Option<Difficulty>,
+
     /// Window height (pixels)
     #[clap(long = "window-height", alias = "wh")]
     window_height: Option<Pixels>,
@@ -53,6 +111,10 @@ struct CommandLineOpts {
     #[clap(long = "window-width", alias = "ww")]
     window_width: Option<Pixels>,
 
+    /// Run in a resizable window instead of fullscreen
+    #[clap(long = "windowed", action)]
+    windowed: bool,
+
     /// Scale factor (1 to 4)
     #[clap(short = 's', long = "scale")]
     scale: Option<usize>,
@@ -78,180 +140,713 @@ struct CommandLineOpts {
         conflicts_with = "classic"
     )]
     brat_damage: usize,
+
+    /// Random seed (reproduces the same maze and game)
+    #[clap(long = "seed")]
+    seed: Option<u64>,
+
+    /// Run the simulation without a window or audio
+    #[clap(long = "headless", action)]
+    headless: bool,
+
+    /// Record commands to a replay file
+    #[clap(long = "record", conflicts_with_all = ["replay", "headless"])]
+    record: Option<PathBuf>,
+
+    /// Play back a recorded game
+    #[clap(long = "replay", conflicts_with = "headless")]
+    replay: Option<PathBuf>,
+
+    /// High score file (default: ~/.rats_high_scores)
+    #[clap(long = "high-scores")]
+    high_scores: Option<PathBuf>,
+
+    /// Key map file (default: ~/.rats_keys.toml)
+    #[clap(long = "keys")]
+    keys: Option<PathBuf>,
+
+    /// Directory for screenshots and GIFs
+    #[clap(long = "capture-dir", default_value = ".")]
+    capture_dir: PathBuf,
+
+    /// Game tuning file (default: ~/.rats.toml)
+    #[clap(long = "config")]
+    config: Option<PathBuf>,
+
+    /// Change a game tuning setting (e.g. --set rat_kill=100)
+    #[clap(long = "set", value_name = "KEY=VALUE")]
+    settings: Vec<String>,
 }
 
+// size of a new maze in the maze editor (maze cells)
+const EDITOR_MAZE_SIZE: Size = 8;
+
 fn main() {
     let mut opts = CommandLineOpts::parse();
     if opts.classic {
         opts.rat_damage = 100;
         opts.brat_damage = 100;
     }
-    if let Err(error) = play(opts) {
+    if let Err(error) = load_config(&opts) {
+        println!("{error}");
+        return;
+    }
+    if let Some(path) = &opts.edit {
+        if let Err(error) = edit(&opts, path) {
+            println!("{error}");
+        }
+    } else if opts.headless {
+        simulate(opts);
+    } else if let Err(error) = play(opts) {
         println!("{error}");
     }
 }
 
-fn play(opts: CommandLineOpts) -> Result<()> {
+// settings from the config file (if any) followed by the ones from the command
+// line
+fn load_config(opts: &CommandLineOpts) -> Result<()> {
+    let mut config = match &opts.config {
+        Some(path) => Config::load(path)?,
+        None => {
+            let path = default_config_path();
+            if path.exists() {
+                Config::load(path)?
+            } else {
+                Config::default()
+            }
+        }
+    };
+    for setting in opts.settings.iter() {
+        let (key, value) = setting.split_once('=').ok_or_else(|| {
+            config_error(format!("invalid setting: {setting}"))
+        })?;
+        config.set(key.trim(), value.trim())?;
+    }
+    set_config(config);
+    Ok(())
+}
+
+fn load_maze_file(opts: &CommandLineOpts) -> Result<Option<MazeFile>> {
+    match &opts.maze_file {
+        Some(path) => Ok(Some(MazeFile::load(path)?)),
+        None => Ok(None),
+    }
+}
+
+fn difficulty(opts: &CommandLineOpts) -> Difficulty {
+    match opts.difficulty {
+        Some(difficulty) => difficulty,
+        None if opts.classic => Difficulty::Classic,
+        None => Difficulty::Normal,
+    }
+}
+
+fn players(opts: &CommandLineOpts) -> usize {
+    if opts.coop {
+        2
+    } else {
+        1
+    }
+}
+
+fn init_options(opts: &CommandLineOpts) -> InitOptions {
+    InitOptions::new()
+        .display_index(opts.display)
+        .window_height(opts.window_height)
+        .window_width(opts.window_width)
+        .scale(opts.scale)
+        .maze_height(opts.maze_height)
+        .maze_width(opts.maze_width)
+        .density(opts.density)
+        .factories(opts.factories)
+        .quiet(opts.classic)
+        .windowed(opts.windowed)
+}
+
+fn play(mut opts: CommandLineOpts) -> Result<()> {
+    let mut replay = match &opts.replay {
+        Some(path) => Some(Replay::load(path)?),
+        None => None,
+    };
+    if let Some(replay) = &replay {
+        // play the recorded game, not the one from the command line
+        opts.seed = Some(replay.seed);
+        opts.maze_height = Some(replay.maze_height);
+        opts.maze_width = Some(replay.maze_width);
+        opts.density = replay.density;
+        opts.factories = replay.factories;
+        opts.classic = replay.classic;
+        opts.coop = replay.players > 1;
+        opts.difficulty = Some(replay.difficulty);
+        opts.algorithm = replay.algorithm;
+        opts.bounded = replay.bounded;
+        opts.rat_damage = replay.rat_damage;
+        opts.brat_damage = replay.brat_damage;
+        set_config(replay.config);
+    }
+
+    // a replay has its own copy of the maze
+    let maze_file = match &replay {
+        Some(replay) => replay.maze_file.clone(),
+        None => load_maze_file(&opts)?,
+    };
+
+    let mut video = video::init(init_options(&opts))?;
+    // recorded games must advance the game clock by the same amount of
+    // time every tick in order to be replayed exactly
+    let tick_ms = match &replay {
+        Some(replay) => replay.tick_ms,
+        None => frame_ms(opts.fps),
+    };
+    let clock: Box<dyn Clock> = if opts.record.is_some() || replay.is_some() {
+        Box::new(SimulatedClock::new(tick_ms))
+    } else {
+        Box::new(RealClock::new())
+    };
+    // a replayed maze has to be exactly the recorded size, not the screen size
+    let (screen_rows, screen_cols) = if replay.is_some() {
+        (0, 0)
+    } else {
+        (video.rows(), video.cols())
+    };
+    let levels = Levels::new(
+        difficulty(&opts),
+        opts.algorithm,
+        opts.bounded,
+        &init_options(&opts),
+        screen_rows,
+        screen_cols,
+        config(),
+    );
     let mut context = GameContext::create(
-        InitOptions::new()
-            .display_index(opts.display)
-            .window_height(opts.window_height)
-            .window_width(opts.window_width)
-            .scale(opts.scale)
-            .maze_height(opts.maze_height)
-            .maze_width(opts.maze_width)
-            .density(opts.density)
-            .factories(opts.factories)
-            .quiet(opts.classic),
+        players(&opts),
+        levels,
         opts.rat_damage,
         opts.brat_damage,
-    )?;
+        opts.seed,
+        clock,
+        maze_file.clone(),
+    );
+    context.split_screen = opts.split_screen;
+    let mut recording = opts.record.as_ref().map(|_| Replay {
+        seed: context.seed,
+        tick_ms,
+        maze_height: context.maze.cell_rows(),
+        maze_width: context.maze.cell_cols(),
+        density: opts.density,
+        factories: opts.factories,
+        classic: opts.classic,
+        players: context.players.len(),
+        difficulty: context.levels.difficulty,
+        algorithm: opts.algorithm,
+        bounded: opts.bounded,
+        maze_file,
+        rat_damage: opts.rat_damage,
+        brat_damage: opts.brat_damage,
+        config: config(),
+        events: VecDeque::new(),
+    });
 
-    let texture_creator = context.video.canvas.texture_creator();
-    let mut textures = vec![];
-    for _ in 0..ATTR_COMBOS {
-        let texture = texture_creator.create_texture_streaming(
-            PixelFormatEnum::RGB24,
-            (CHAR_CELL_WIDTH * context.video.scale) as u32,
-            (FONT_SIZE as usize * CHAR_CELL_HEIGHT * context.video.scale)
-                as u32,
-        )?;
-        textures.push(texture);
-    }
-    context.video.init_charmap_textures(
-        &mut textures,
-        context.video.scale,
-        !opts.classic,
+    let mut high_scores = HighScores::load(
+        opts.high_scores
+            .clone()
+            .unwrap_or_else(default_high_scores_path),
     )?;
+    let mut score_checked = false;
+    let mut keymap =
+        KeyMap::load(opts.keys.clone().unwrap_or_else(default_keys_path))?;
+    let mut settings = Settings::new();
+    // the state the game goes back to when leaving the settings screen
+    let mut settings_return = GameState::Paused;
 
-    let nanos_per_frame = if opts.fps > 0 {
-        (1_000_000_000 / opts.fps) as u32
-    } else {
-        0
-    };
+    let texture_creator = video.canvas.texture_creator();
+    let mut textures =
+        charmap_textures(&video, &texture_creator, !opts.classic)?;
+    let mut capture =
+        Capture::new(&opts.capture_dir, video.scale, !opts.classic);
+
+    let nanos_per_frame =
+        1_000_000_000_usize.checked_div(opts.fps).unwrap_or(0) as u32;
     let mut frame_time = Instant::now();
-    let mut event_pump = context.video.sdl.event_pump().map_err(sdl_error)?;
-    let mut rat_spawn_time =
-        Instant::now() - Duration::new(RAT_SPAWN_SECONDS, 0);
-    let mut brat_spawn_time = Instant::now();
+    let mut event_pump = video.sdl.event_pump().map_err(sdl_error)?;
+    let mut gamepads = Gamepads::new();
     while context.game_state != GameState::Quit {
-        context.render_frame(&textures, opts.classic)?;
+        context.render_frame(&mut video.buffer, opts.classic, &high_scores);
+        if context.game_state == GameState::Settings {
+            settings.render(&mut video.buffer, &keymap);
+        }
+        video.render_buffer(&textures)?;
+        match capture.frame(&video.buffer) {
+            Ok(Some(path)) => println!("recording saved: {}", path.display()),
+            Ok(None) => {}
+            Err(error) => println!("recording error: {error}"),
+        }
         for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. } => context.game_state = GameState::Quit,
+            let commands = match event {
+                Event::Quit { .. } => {
+                    context.game_state = GameState::Quit;
+                    vec![]
+                }
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(width, height),
+                    ..
+                } => {
+                    if video.resize(width as Pixels, height as Pixels) {
+                        textures = charmap_textures(
+                            &video,
+                            &texture_creator,
+                            !opts.classic,
+                        )?;
+                        capture.set_scale(video.scale, !opts.classic);
+                    }
+                    vec![]
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } if context.game_state == GameState::EnterInitials => {
+                    enter_initials(
+                        &mut context,
+                        &mut high_scores,
+                        keycode,
+                        opts.classic,
+                    );
+                    vec![]
+                }
                 Event::KeyDown {
                     keycode: Some(keycode),
                     ..
-                } => key_down(&mut context, keycode),
+                } if context.game_state == GameState::Settings => {
+                    if settings.key_down(&mut keymap, keycode) {
+                        if let Err(error) = keymap.save() {
+                            println!("key map error: {error}");
+                        }
+                        context.game_state = settings_return;
+                    }
+                    vec![]
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } => keymap
+                    .command(keycode)
+                    .map(|command| (true, command))
+                    .into_iter()
+                    .collect(),
                 Event::KeyUp {
                     keycode: Some(keycode),
                     ..
-                } => key_up(&mut context, keycode),
-                _ => {}
+                } => keymap
+                    .command(keycode)
+                    .map(|command| (false, command))
+                    .into_iter()
+                    .collect(),
+                Event::ControllerDeviceAdded { which, .. } => {
+                    gamepads.add(&video, which);
+                    vec![]
+                }
+                Event::ControllerDeviceRemoved { which, .. } => {
+                    gamepads.remove(which)
+                }
+                Event::ControllerAxisMotion {
+                    which, axis, value, ..
+                } => gamepads.axis(which, axis, value),
+                Event::ControllerButtonDown { which, button, .. } => {
+                    gamepads.button(which, button, true)
+                }
+                Event::ControllerButtonUp { which, button, .. } => {
+                    gamepads.button(which, button, false)
+                }
+                _ => vec![],
+            };
+            for (down, command) in commands {
+                // initials and key changes can only be typed in on the
+                // keyboard
+                if down
+                    && matches!(
+                        context.game_state,
+                        GameState::EnterInitials | GameState::Settings
+                    )
+                {
+                    continue;
+                }
+                // the window and captures don't affect the game so they
+                // aren't recorded
+                if matches!(
+                    command,
+                    Command::Fullscreen
+                        | Command::Screenshot
+                        | Command::RecordGif
+                        | Command::SaveMaze
+                ) {
+                    if down {
+                        window_command(
+                            &mut video,
+                            &mut capture,
+                            &context,
+                            command,
+                        )?;
+                    }
+                    continue;
+                }
+                // the game clock is stopped on the settings screen and the
+                // game goes back to the state it was in, so it doesn't need
+                // to be recorded
+                if command == Command::Settings {
+                    if down
+                        && matches!(
+                            context.game_state,
+                            GameState::Running | GameState::Paused
+                        )
+                    {
+                        settings_return = context.game_state;
+                        context.game_state = GameState::Settings;
+                    }
+                    continue;
+                }
+                if let Some(recording) = &mut recording {
+                    recording.record(context.ticks, down, command);
+                }
+                if down && (replay.is_none() || replay_command(command)) {
+                    command_down(&mut context, command);
+                } else if !down && replay.is_none() {
+                    command_up(&mut context, command);
+                }
+            }
+        }
+        if let Some(replay) = &mut replay {
+            while let Some(event) = replay.next_event(context.ticks) {
+                if event.down {
+                    command_down(&mut context, event.command);
+                } else {
+                    command_up(&mut context, event.command);
+                }
             }
         }
         if context.game_state == GameState::Restart {
-            context.new_game(
-                InitOptions::new()
-                    .display_index(opts.display)
-                    .window_height(opts.window_height)
-                    .window_width(opts.window_width)
-                    .scale(opts.scale)
-                    .maze_height(opts.maze_height)
-                    .maze_width(opts.maze_width)
-                    .density(opts.density)
-                    .factories(opts.factories),
-            );
-            rat_spawn_time =
-                Instant::now() - Duration::new(RAT_SPAWN_SECONDS, 0);
+            context.new_game();
+            score_checked = false;
         }
 
-        context.update();
-        if context.firing_dir != dir::NONE
-            && context.bullet_fire_start.elapsed() >= context.bullet_firing_time
-        {
-            context.fire();
-        }
-        if rat_spawn_time.elapsed().as_secs() >= RAT_SPAWN_SECONDS {
-            context.new_rats =
-                (context.live_factories as f32 * RATS_PER_FACTORY) as usize;
-            rat_spawn_time = Instant::now();
-        }
-        if context.live_rats > 0
-            && brat_spawn_time.elapsed().as_secs() >= BRAT_SPAWN_SECONDS
-        {
-            // Breed you little bastards!
-            context.new_brats = context.live_rats / 8 + random(2, 10);
-            brat_spawn_time = Instant::now();
+        context.step(opts.classic);
+        for sound in context.sounds.drain(..) {
+            video.play_sound(sound);
         }
-        if context.game_state != GameState::Quit
-            && ((context.live_factories == 0 && context.live_rats == 0)
-                || context.players_left == 0)
-        {
-            if !opts.classic {
-                context.score += context.live_brats * BRAT_KILL;
-                context.dead_brats += context.live_brats;
-                context.live_brats = 0;
+        if context.game_state == GameState::Finished && !score_checked {
+            score_checked = true;
+            if replay.is_none() && high_scores.qualifies(context.final_score())
+            {
+                context.game_state = GameState::EnterInitials;
             }
-            if context.live_brats == 0 {
-                if context.game_state != GameState::Finished {
-                    context.time = context.start.elapsed().as_secs() as usize;
+        }
+
+        limit_frame_rate(frame_time, nanos_per_frame);
+        frame_time = Instant::now();
+    }
+
+    if let (Some(path), Some(recording)) = (&opts.record, &recording) {
+        recording.save(path)?;
+    }
+    if capture.is_recording() {
+        window_command(&mut video, &mut capture, &context, Command::RecordGif)?;
+    }
+    Ok(())
+}
+
+// the maze editor runs in its own loop, it only needs a window
+fn edit(opts: &CommandLineOpts, path: &Path) -> Result<()> {
+    let mut editor = Editor::new(
+        path,
+        opts.maze_height.unwrap_or(EDITOR_MAZE_SIZE),
+        opts.maze_width.unwrap_or(EDITOR_MAZE_SIZE),
+    )?;
+    let keymap =
+        KeyMap::load(opts.keys.clone().unwrap_or_else(default_keys_path))?;
+    let mut video = video::init(init_options(opts))?;
+    let texture_creator = video.canvas.texture_creator();
+    let mut textures =
+        charmap_textures(&video, &texture_creator, !opts.classic)?;
+    let nanos_per_frame =
+        1_000_000_000_usize.checked_div(opts.fps).unwrap_or(0) as u32;
+    let mut frame_time = Instant::now();
+    let mut event_pump = video.sdl.event_pump().map_err(sdl_error)?;
+    loop {
+        editor.render(&mut video.buffer);
+        video.render_buffer(&textures)?;
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. } => return Ok(()),
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(width, height),
+                    ..
+                } if video.resize(width as Pixels, height as Pixels) => {
+                    textures = charmap_textures(
+                        &video,
+                        &texture_creator,
+                        !opts.classic,
+                    )?;
                 }
-                context.game_state = GameState::Finished;
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    if keymap.command(keycode) == Some(Command::Fullscreen) {
+                        video.toggle_fullscreen()?;
+                    } else if editor.key_down(keycode) {
+                        return Ok(());
+                    }
+                }
+                _ => {}
             }
         }
+        limit_frame_rate(frame_time, nanos_per_frame);
+        frame_time = Instant::now();
+    }
+}
 
-        if nanos_per_frame > 0 {
-            // don't hog the CPU
-            let nanos_elapsed = frame_time.elapsed().as_nanos() as u32;
-            if nanos_elapsed < nanos_per_frame {
-                sleep(Duration::new(0, nanos_per_frame - nanos_elapsed));
+// fullscreen and capture commands, failed captures aren't worth quitting the
+// game over
+fn window_command(
+    video: &mut Video,
+    capture: &mut Capture,
+    context: &GameContext,
+    command: Command,
+) -> Result<()> {
+    match command {
+        Command::Fullscreen => video.toggle_fullscreen()?,
+        Command::Screenshot => match capture.screenshot(&video.buffer) {
+            Ok(path) => println!("screenshot saved: {}", path.display()),
+            Err(error) => println!("screenshot error: {error}"),
+        },
+        Command::SaveMaze => match capture.save_maze(&context.to_maze_file()) {
+            Ok(path) => println!("maze saved: {}", path.display()),
+            Err(error) => println!("maze error: {error}"),
+        },
+        Command::RecordGif => {
+            let recording = capture.is_recording();
+            match capture.toggle_recording(&video.buffer) {
+                Ok(path) if recording => {
+                    println!("recording saved: {}", path.display())
+                }
+                Ok(path) => println!("recording: {}", path.display()),
+                Err(error) => println!("recording error: {error}"),
             }
         }
+        _ => {}
+    }
+    Ok(())
+}
 
-        frame_time = Instant::now();
+// one texture for every attribute combination holding the whole character
+// set at the current scale
+fn charmap_textures<'a>(
+    video: &Video,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    color: bool,
+) -> Result<Vec<Texture<'a>>> {
+    let mut textures = vec![];
+    for _ in 0..ATTR_COMBOS {
+        let texture = texture_creator
+            .create_texture_streaming(
+                PixelFormatEnum::RGB24,
+                (CHAR_CELL_WIDTH * video.scale) as u32,
+                (FONT_SIZE as usize * CHAR_CELL_HEIGHT * video.scale) as u32,
+            )
+            .map_err(VideoError::from)?;
+        textures.push(texture);
     }
+    video.init_charmap_textures(&mut textures, video.scale, color)?;
+    Ok(textures)
+}
 
-    Ok(())
+// run the game without a window or audio until it's over, as fast as
+// possible, advancing the game clock by one frame's worth of time each step
+fn simulate(opts: CommandLineOpts) {
+    let context = match headless_game(&opts) {
+        Ok(context) => context,
+        Err(error) => {
+            println!("{error}");
+            return;
+        }
+    };
+    println!(
+        "seed: {seed}, score: {score}, time: {time}, level: {level}, \
+        rats: {rats}, brats: {brats}, factories: {factories}, \
+        players: {players}",
+        seed = context.seed,
+        level = context.level,
+        score = context.score,
+        time = context.time,
+        rats = context.dead_rats,
+        brats = context.dead_brats,
+        factories = context.dead_factories,
+        players = context.players_dead,
+    );
+}
+
+// a whole game played without video or input (the players never move or
+// fire, so the rats always win in the end)
+fn headless_game(opts: &CommandLineOpts) -> Result<GameContext> {
+    let maze_file = load_maze_file(opts)?;
+    let levels = Levels::new(
+        difficulty(opts),
+        opts.algorithm,
+        opts.bounded,
+        &init_options(opts),
+        0,
+        0,
+        config(),
+    );
+    let mut context = GameContext::create(
+        players(opts),
+        levels,
+        opts.rat_damage,
+        opts.brat_damage,
+        opts.seed,
+        Box::new(SimulatedClock::new(frame_ms(opts.fps))),
+        maze_file,
+    );
+    while context.game_state == GameState::Running {
+        context.step(opts.classic);
+        context.sounds.clear();
+    }
+    Ok(context)
 }
 
-fn key_down(context: &mut GameContext, keycode: Keycode) {
+// length of a frame in milliseconds, at least one so that the game clock
+// keeps moving at more than 1000 frames per second
+fn frame_ms(fps: usize) -> u32 {
+    (1000_usize.checked_div(fps).unwrap_or(1) as u32).max(1)
+}
+
+fn limit_frame_rate(frame_time: Instant, nanos_per_frame: u32) {
+    if nanos_per_frame > 0 {
+        // don't hog the CPU
+        let nanos_elapsed = frame_time.elapsed().as_nanos() as u32;
+        if nanos_elapsed < nanos_per_frame {
+            sleep(Duration::new(0, nanos_per_frame - nanos_elapsed));
+        }
+    }
+}
+
+fn enter_initials(
+    context: &mut GameContext,
+    high_scores: &mut HighScores,
+    keycode: Keycode,
+    classic: bool,
+) {
     match keycode {
-        Keycode::Escape => context.game_state = GameState::Quit,
-        Keycode::Space => match context.game_state {
+        Keycode::Return | Keycode::KpEnter if !context.initials.is_empty() => {
+            high_scores.insert(HighScore {
+                initials: context.initials.clone(),
+                score: context.final_score(),
+                date: now(),
+                maze_height: context.maze.cell_rows(),
+                maze_width: context.maze.cell_cols(),
+                density: context.density,
+                factories: context.factories,
+                classic,
+                time: context.time,
+            });
+            if let Err(error) = high_scores.save() {
+                println!("high score error: {error}");
+            }
+            context.game_state = GameState::Finished;
+        }
+        Keycode::Escape => context.game_state = GameState::Finished,
+        Keycode::Backspace => {
+            context.initials.pop();
+        }
+        _ => {
+            let name = keycode.name();
+            let mut chars = name.chars();
+            if let (Some(ch), None) = (chars.next(), chars.next()) {
+                if ch.is_ascii_alphanumeric()
+                    && context.initials.len() < INITIALS_LENGTH
+                {
+                    context.initials.push(ch.to_ascii_uppercase());
+                }
+            }
+        }
+    }
+}
+
+// commands that still work while watching a replay
+fn replay_command(command: Command) -> bool {
+    matches!(
+        command,
+        Command::Quit
+            | Command::Pause
+            | Command::Diagnostics
+            | Command::Minimap
+    )
+}
+
+// player 2 commands only do something in co-op games
+fn command_down(context: &mut GameContext, command: Command) {
+    let players = context.players.len();
+    match command {
+        Command::Quit => context.game_state = GameState::Quit,
+        Command::Pause => match context.game_state {
             GameState::Running => context.game_state = GameState::Paused,
             GameState::Paused => context.game_state = GameState::Running,
             _ => {}
         },
-        Keycode::F12 => context.diagnostics = !context.diagnostics,
-        Keycode::Up => context.start(dir::UP),
-        Keycode::Down => context.start(dir::DOWN),
-        Keycode::Left => context.start(dir::LEFT),
-        Keycode::Right => context.start(dir::RIGHT),
-        Keycode::W => context.start_firing(dir::UP),
-        Keycode::A => context.start_firing(dir::LEFT),
-        Keycode::S => context.start_firing(dir::DOWN),
-        Keycode::D => context.start_firing(dir::RIGHT),
-        Keycode::Y if context.game_state == GameState::Finished => {
+        Command::Diagnostics => context.diagnostics = !context.diagnostics,
+        Command::Minimap => context.minimap = !context.minimap,
+        Command::Move(player, dir) if player < players => {
+            context.start(player, dir)
+        }
+        Command::Fire(player, dir) if player < players => {
+            context.start_firing(player, dir)
+        }
+        Command::PlayAgain if context.game_state == GameState::Finished => {
             context.game_state = GameState::Restart;
         }
-        Keycode::N if context.game_state == GameState::Finished => {
+        Command::DontPlayAgain if context.game_state == GameState::Finished => {
             context.game_state = GameState::Quit;
         }
         _ => {}
     }
 }
 
-fn key_up(context: &mut GameContext, keycode: Keycode) {
-    match keycode {
-        Keycode::Up => context.stop(dir::UP),
-        Keycode::Down => context.stop(dir::DOWN),
-        Keycode::Left => context.stop(dir::LEFT),
-        Keycode::Right => context.stop(dir::RIGHT),
-        Keycode::W => context.stop_firing(dir::UP),
-        Keycode::A => context.stop_firing(dir::LEFT),
-        Keycode::S => context.stop_firing(dir::DOWN),
-        Keycode::D => context.stop_firing(dir::RIGHT),
+fn command_up(context: &mut GameContext, command: Command) {
+    let players = context.players.len();
+    match command {
+        Command::Move(player, dir) if player < players => {
+            context.stop(player, dir)
+        }
+        Command::Fire(player, dir) if player < players => {
+            context.stop_firing(player, dir)
+        }
         _ => {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_game_finishes() {
+        for seed in ["1", "2", "3"] {
+            let opts = CommandLineOpts::parse_from([
+                "rats",
+                "--headless",
+                "--seed",
+                seed,
+            ]);
+            let context = headless_game(&opts).unwrap();
+            assert_eq!(context.game_state, GameState::Finished);
+            assert_eq!(context.players_left, 0);
+        }
+    }
+
+    #[test]
+    fn frames_take_time() {
+        assert_eq!(frame_ms(60), 16);
+        assert_eq!(frame_ms(1000), 1);
+        assert_eq!(frame_ms(5000), 1);
+        assert_eq!(frame_ms(0), 1);
+    }
+}
@@ -0,0 +1,263 @@
+use super::{MazeGrid, Walls};
+use crate::{
+    errors::{maze_error, Result},
+    game_context::MAX_PLAYERS,
+    levels::MAX_MAZE_CELLS,
+};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+use video::{Pos, Size};
+
+const MAZE_HEADER: &str = "rats maze 1";
+
+// A maze file is a plain text file with the size of the maze (in maze cells)
+// followed by a picture of the maze. Every maze cell is drawn as two lines of
+// three characters: a corner and its top wall (-- or blanks) on the first line
+// and its left wall (| or a blank) followed by two markers on the second. A
+// marker is F for a rat factory, 1 or 2 for where a player starts or a blank.
+// Each marker can only be used once in a maze cell and players can't start
+// in a maze cell with a factory.
+//
+//   rats maze 1
+//   size 2 3
+//   +--+--+--
+//   |1    |F
+//   +  +--+
+//   |  |F
+//
+// The maze wraps around, so the top walls of the first row are also the bottom
+// of the maze and the left walls of the first column are also its right side.
+// Trailing blanks can be left out.
+//
+// Maze files don't record whether a maze is bounded. A saved bounded maze
+// keeps its walls along the top and left edges, but it only gets the rest of
+// its outer wall back when played with --bounded. A maze cell also only has
+// room for two markers, so saving leaves out any more than that.
+#[derive(Clone, Debug)]
+pub struct MazeFile {
+    pub grid: MazeGrid,
+    // maze cells (row, col) with a factory
+    pub factories: Vec<(Pos, Pos)>,
+    // maze cell each player starts in (if given)
+    pub starts: [Option<(Pos, Pos)>; MAX_PLAYERS],
+}
+
+impl MazeFile {
+    // every maze cell surrounded by walls, with nothing in it
+    pub fn new(rows: Size, cols: Size) -> MazeFile {
+        MazeFile {
+            grid: MazeGrid::walled(rows, cols),
+            factories: vec![],
+            starts: [None; MAX_PLAYERS],
+        }
+    }
+
+    pub fn rows(&self) -> Size {
+        self.grid.rows()
+    }
+
+    pub fn cols(&self) -> Size {
+        self.grid.cols()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<MazeFile> {
+        MazeFile::read(&mut BufReader::new(File::open(path)?).lines())
+    }
+
+    // reads just the lines of the maze so that mazes can be part of other
+    // files (replays)
+    pub fn read<I>(lines: &mut I) -> Result<MazeFile>
+    where
+        I: Iterator<Item = io::Result<String>>,
+    {
+        match lines.next() {
+            Some(Ok(line)) if line == MAZE_HEADER => {}
+            _ => return Err(maze_error("not a maze file")),
+        }
+        let (rows, cols) = match lines.next() {
+            Some(line) => {
+                let line = line?;
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                match fields[..] {
+                    ["size", rows, cols] => (parse(rows)?, parse(cols)?),
+                    _ => return Err(maze_error("missing maze size")),
+                }
+            }
+            None => return Err(maze_error("missing maze size")),
+        };
+        if !(2..=MAX_MAZE_CELLS).contains(&rows)
+            || !(2..=MAX_MAZE_CELLS).contains(&cols)
+        {
+            return Err(maze_error("invalid maze size"));
+        }
+        let mut maze_file = MazeFile::new(rows, cols);
+        let width = cols as usize * 3;
+        for row in 0..rows {
+            let mut picture = [String::new(), String::new()];
+            for line in picture.iter_mut() {
+                *line = match lines.next() {
+                    Some(line) => line?,
+                    None => return Err(maze_error("maze is too short")),
+                };
+                if !line.is_ascii() || line.len() > width {
+                    return Err(maze_error(format!("invalid line: {line}")));
+                }
+            }
+            let [walls, cells] =
+                picture.map(|line| format!("{line:width$}").into_bytes());
+            for col in 0..cols {
+                let index = col as usize * 3;
+                maze_file.grid.set(
+                    row,
+                    col,
+                    Walls {
+                        top: walls[index + 1] != b' ',
+                        left: cells[index] != b' ',
+                    },
+                );
+                let mut factory = false;
+                let mut start = false;
+                for &marker in &cells[index + 1..index + 3] {
+                    let duplicate = match marker {
+                        b'F' => std::mem::replace(&mut factory, true),
+                        b'1' | b'2' => {
+                            let player = (marker - b'1') as usize;
+                            start = true;
+                            maze_file.starts[player]
+                                .replace((row, col))
+                                .is_some()
+                        }
+                        b' ' => false,
+                        _ => {
+                            return Err(maze_error(format!(
+                                "unknown marker: {}",
+                                marker as char
+                            )))
+                        }
+                    };
+                    if duplicate {
+                        return Err(maze_error(format!(
+                            "duplicate marker: {}",
+                            marker as char
+                        )));
+                    }
+                }
+                // the player would be killed by the factory straight away
+                if factory && start {
+                    return Err(maze_error(format!(
+                        "player starts on a factory: row {row}, column {col}"
+                    )));
+                }
+                if factory {
+                    maze_file.factories.push((row, col));
+                }
+            }
+        }
+        Ok(maze_file)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    pub fn write<W: Write>(&self, file: &mut W) -> Result<()> {
+        writeln!(file, "{MAZE_HEADER}")?;
+        writeln!(file, "size {} {}", self.rows(), self.cols())?;
+        for row in 0..self.rows() {
+            let mut walls = String::new();
+            let mut cells = String::new();
+            for col in 0..self.cols() {
+                let cell = self.grid.get(row, col);
+                walls.push_str(if cell.top { "+--" } else { "+  " });
+                cells.push(if cell.left { '|' } else { ' ' });
+                let mut markers = vec![];
+                for (player, start) in self.starts.iter().enumerate() {
+                    if *start == Some((row, col)) {
+                        markers.push((b'1' + player as u8) as char);
+                    }
+                }
+                if self.factories.contains(&(row, col)) {
+                    markers.push('F');
+                }
+                cells.extend(markers.iter().chain([' ', ' '].iter()).take(2));
+            }
+            writeln!(file, "{}", walls.trim_end())?;
+            writeln!(file, "{}", cells.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+fn parse(field: &str) -> Result<Size> {
+    field
+        .parse()
+        .map_err(|_| maze_error(format!("invalid value: {field}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const MAZE: &str =
+        "rats maze 1\nsize 2 3\n+--+--+--\n|1    |F\n+  +--+\n|  |F\n";
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir()
+            .join(format!("rats-maze-{}-{name}", std::process::id()))
+    }
+
+    fn load_str(name: &str, contents: &str) -> Result<MazeFile> {
+        let path = temp_path(name);
+        fs::write(&path, contents)?;
+        let maze_file = MazeFile::load(&path);
+        fs::remove_file(&path)?;
+        maze_file
+    }
+
+    fn save_str(name: &str, maze_file: &MazeFile) -> String {
+        let path = temp_path(name);
+        maze_file.save(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        saved
+    }
+
+    #[test]
+    fn load_and_save() {
+        // trailing blanks are the same as none at all
+        let padded: String =
+            MAZE.lines().map(|line| format!("{line:9}\n")).collect();
+        let maze_file = load_str("padded", &padded).unwrap();
+        assert_eq!(maze_file.factories, [(0, 2), (1, 1)]);
+        assert_eq!(maze_file.starts, [Some((0, 0)), None]);
+        assert!(!maze_file.grid.get(1, 0).top);
+        assert!(maze_file.grid.get(1, 1).left);
+
+        let saved = save_str("saved", &maze_file);
+        assert_eq!(saved, MAZE);
+        let reloaded = load_str("reloaded", &saved).unwrap();
+        assert_eq!(save_str("resaved", &reloaded), MAZE);
+    }
+
+    #[test]
+    fn load_rejects_bad_markers() {
+        for (name, cells) in [
+            ("factory_start", "|F1"),
+            ("start_factory", "|2F"),
+            ("two_factories", "|FF"),
+            ("two_starts", "|1 |1"),
+            ("unknown", "|X"),
+        ] {
+            let maze = format!("rats maze 1\nsize 2 2\n+--+--\n{cells}\n");
+            let maze = format!("{maze}+--+--\n|  |\n");
+            assert!(load_str(name, &maze).is_err(), "{name}");
+        }
+    }
+}
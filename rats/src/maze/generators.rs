@@ -0,0 +1,581 @@
+use super::MazeGrid;
+use crate::game_context::GameRng;
+use clap::ValueEnum;
+use rand::{seq::SliceRandom, Rng};
+use video::{Pos, Size};
+
+// Maze generators carve passages into a MazeGrid where every cell starts out
+// surrounded by walls. The grid wraps around but passages are only carved
+// between cells that are next to each other without wrapping, so the walls
+// along the top and left edges (which are also the bottom and right edges)
+// stay in place until the maze density knocks some of them out. Every random
+// decision is drawn from the game's RNG so that a given seed always produces
+// the same maze.
+pub trait MazeGenerator {
+    fn generate(&self, rows: Size, cols: Size, rng: &mut GameRng) -> MazeGrid;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum MazeAlgorithm {
+    #[default]
+    HuntAndKill,
+    Backtracker,
+    Prims,
+    Kruskals,
+    Ellers,
+    BinaryTree,
+    GrowingTree,
+    Rooms,
+    // a different algorithm every level
+    Mixed,
+}
+
+// the algorithms mixed mazes cycle through
+const ALGORITHMS: [MazeAlgorithm; 8] = [
+    MazeAlgorithm::HuntAndKill,
+    MazeAlgorithm::Backtracker,
+    MazeAlgorithm::Prims,
+    MazeAlgorithm::Kruskals,
+    MazeAlgorithm::Ellers,
+    MazeAlgorithm::BinaryTree,
+    MazeAlgorithm::GrowingTree,
+    MazeAlgorithm::Rooms,
+];
+
+impl MazeAlgorithm {
+    pub fn name(self) -> &'static str {
+        match self {
+            MazeAlgorithm::HuntAndKill => "hunt-and-kill",
+            MazeAlgorithm::Backtracker => "backtracker",
+            MazeAlgorithm::Prims => "prims",
+            MazeAlgorithm::Kruskals => "kruskals",
+            MazeAlgorithm::Ellers => "ellers",
+            MazeAlgorithm::BinaryTree => "binary-tree",
+            MazeAlgorithm::GrowingTree => "growing-tree",
+            MazeAlgorithm::Rooms => "rooms",
+            MazeAlgorithm::Mixed => "mixed",
+        }
+    }
+
+    // the algorithm used on a level (numbered from 1)
+    pub fn for_level(self, number: usize) -> MazeAlgorithm {
+        match self {
+            MazeAlgorithm::Mixed => {
+                ALGORITHMS[number.saturating_sub(1) % ALGORITHMS.len()]
+            }
+            algorithm => algorithm,
+        }
+    }
+
+    pub fn generator(self) -> Box<dyn MazeGenerator> {
+        match self.for_level(1) {
+            MazeAlgorithm::Backtracker => Box::new(Backtracker),
+            MazeAlgorithm::Prims => Box::new(Prims),
+            MazeAlgorithm::Kruskals => Box::new(Kruskals),
+            MazeAlgorithm::Ellers => Box::new(Ellers),
+            MazeAlgorithm::BinaryTree => Box::new(BinaryTree),
+            MazeAlgorithm::GrowingTree => Box::new(GrowingTree),
+            MazeAlgorithm::Rooms => Box::new(Rooms),
+            _ => Box::new(HuntAndKill),
+        }
+    }
+}
+
+const CARVES: [Carve; 4] = [Carve::Up, Carve::Down, Carve::Left, Carve::Right];
+
+// "Hunt & Kill" maze generation
+//
+// Random walk carving passages to unvisited neighbors until we hit a dead end,
+// then scan the grid for an unvisited cell next to a visited one, connect the
+// two and start walking again from there.
+pub struct HuntAndKill;
+
+impl MazeGenerator for HuntAndKill {
+    fn generate(&self, rows: Size, cols: Size, rng: &mut GameRng) -> MazeGrid {
+        let mut maze_grid = MazeGrid::walled(rows, cols);
+        let mut visited = vec![false; (rows * cols) as usize];
+        let index = |row: Pos, col: Pos| (row * cols + col) as usize;
+        let mut row = rng.gen_range(0..rows);
+        let mut col = rng.gen_range(0..cols);
+        visited[index(row, col)] = true;
+        let mut hunt_start = 0;
+        loop {
+            // walk
+            let mut directions = CARVES;
+            directions.shuffle(rng);
+            let next = directions.into_iter().find_map(|carve| {
+                carve
+                    .neighbor(row, col, rows, cols)
+                    .filter(|&(r, c)| !visited[index(r, c)])
+                    .map(|next| (carve, next))
+            });
+            if let Some((carve, (next_row, next_col))) = next {
+                maze_grid.carve(row, col, carve);
+                row = next_row;
+                col = next_col;
+                visited[index(row, col)] = true;
+                continue;
+            }
+            // hunt
+            let mut found = None;
+            let mut next_hunt_start = hunt_start;
+            'hunt: for r in hunt_start..rows {
+                let mut unvisited = 0;
+                for c in 0..cols {
+                    if visited[index(r, c)] {
+                        continue;
+                    }
+                    unvisited += 1;
+                    // connect to a random visited neighbor, always trying
+                    // the same side first would line the passages up
+                    let mut directions = CARVES;
+                    directions.shuffle(rng);
+                    for carve in directions {
+                        if let Some((nr, nc)) = carve.neighbor(r, c, rows, cols)
+                        {
+                            if visited[index(nr, nc)] {
+                                maze_grid.carve(r, c, carve);
+                                found = Some((r, c));
+                                break 'hunt;
+                            }
+                        }
+                    }
+                }
+                // skip fully visited rows on the next hunt
+                if unvisited == 0 && r == next_hunt_start {
+                    next_hunt_start = r + 1;
+                }
+            }
+            hunt_start = next_hunt_start;
+            match found {
+                Some((r, c)) => {
+                    row = r;
+                    col = c;
+                    visited[index(row, col)] = true;
+                }
+                None => break,
+            }
+        }
+        maze_grid
+    }
+}
+
+// "Recursive Backtracker" maze generation
+//
+// Random walk carving passages to unvisited neighbors, backing up along the
+// path walked so far whenever it hits a dead end. Makes long winding passages
+// with few dead ends.
+pub struct Backtracker;
+
+impl MazeGenerator for Backtracker {
+    fn generate(&self, rows: Size, cols: Size, rng: &mut GameRng) -> MazeGrid {
+        let mut maze_grid = MazeGrid::walled(rows, cols);
+        let mut visited = vec![false; (rows * cols) as usize];
+        let start = (rng.gen_range(0..rows), rng.gen_range(0..cols));
+        visited[(start.0 * cols + start.1) as usize] = true;
+        let mut path = vec![start];
+        while let Some(&(row, col)) = path.last() {
+            match unvisited_neighbor(row, col, rows, cols, &visited, rng) {
+                Some((carve, next_row, next_col)) => {
+                    maze_grid.carve(row, col, carve);
+                    visited[(next_row * cols + next_col) as usize] = true;
+                    path.push((next_row, next_col));
+                }
+                None => {
+                    path.pop();
+                }
+            }
+        }
+        maze_grid
+    }
+}
+
+// "Prim's" maze generation
+//
+// Grow the maze from a single cell by knocking down a random wall between the
+// maze and a cell that isn't part of it yet. Makes lots of short dead ends.
+pub struct Prims;
+
+impl MazeGenerator for Prims {
+    fn generate(&self, rows: Size, cols: Size, rng: &mut GameRng) -> MazeGrid {
+        let mut maze_grid = MazeGrid::walled(rows, cols);
+        let mut visited = vec![false; (rows * cols) as usize];
+        // walls between a cell in the maze and one of its neighbors
+        let mut frontier = vec![];
+        let add = |row: Pos, col: Pos, frontier: &mut Vec<_>| {
+            for carve in CARVES {
+                if carve.neighbor(row, col, rows, cols).is_some() {
+                    frontier.push((row, col, carve));
+                }
+            }
+        };
+        let (row, col) = (rng.gen_range(0..rows), rng.gen_range(0..cols));
+        visited[(row * cols + col) as usize] = true;
+        add(row, col, &mut frontier);
+        while !frontier.is_empty() {
+            let index = rng.gen_range(0..frontier.len());
+            let (row, col, carve) = frontier.swap_remove(index);
+            let Some((next_row, next_col)) =
+                carve.neighbor(row, col, rows, cols)
+            else {
+                continue;
+            };
+            if !visited[(next_row * cols + next_col) as usize] {
+                maze_grid.carve(row, col, carve);
+                visited[(next_row * cols + next_col) as usize] = true;
+                add(next_row, next_col, &mut frontier);
+            }
+        }
+        maze_grid
+    }
+}
+
+// "Kruskal's" maze generation
+//
+// Knock down every wall in random order unless the cells on both sides are
+// already connected.
+pub struct Kruskals;
+
+impl MazeGenerator for Kruskals {
+    fn generate(&self, rows: Size, cols: Size, rng: &mut GameRng) -> MazeGrid {
+        let mut maze_grid = MazeGrid::walled(rows, cols);
+        let mut walls = vec![];
+        for row in 0..rows {
+            for col in 0..cols {
+                for carve in [Carve::Down, Carve::Right] {
+                    if carve.neighbor(row, col, rows, cols).is_some() {
+                        walls.push((row, col, carve));
+                    }
+                }
+            }
+        }
+        walls.shuffle(rng);
+        // every cell starts out in its own set of connected cells
+        let mut sets: Vec<usize> = (0..(rows * cols) as usize).collect();
+        fn find(sets: &mut [usize], cell: usize) -> usize {
+            let mut root = cell;
+            while sets[root] != root {
+                root = sets[root];
+            }
+            sets[cell] = root;
+            root
+        }
+        for (row, col, carve) in walls {
+            let Some((next_row, next_col)) =
+                carve.neighbor(row, col, rows, cols)
+            else {
+                continue;
+            };
+            let set = find(&mut sets, (row * cols + col) as usize);
+            let next_set =
+                find(&mut sets, (next_row * cols + next_col) as usize);
+            if set != next_set {
+                sets[next_set] = set;
+                maze_grid.carve(row, col, carve);
+            }
+        }
+        maze_grid
+    }
+}
+
+// "Eller's" maze generation
+//
+// One row at a time: randomly join neighboring cells that aren't connected
+// yet, then carve at least one passage down from every set of connected
+// cells. The last row joins everything that's left.
+pub struct Ellers;
+
+impl MazeGenerator for Ellers {
+    fn generate(&self, rows: Size, cols: Size, rng: &mut GameRng) -> MazeGrid {
+        let mut maze_grid = MazeGrid::walled(rows, cols);
+        let mut sets: Vec<usize> = (0..cols as usize).collect();
+        let mut next_set = cols as usize;
+        for row in 0..rows {
+            let last_row = row == rows - 1;
+            for col in 0..cols - 1 {
+                let (set, right) = (sets[col as usize], sets[col as usize + 1]);
+                if set != right && (last_row || rng.gen_bool(0.5)) {
+                    maze_grid.carve(row, col, Carve::Right);
+                    for other in sets.iter_mut() {
+                        if *other == right {
+                            *other = set;
+                        }
+                    }
+                }
+            }
+            if last_row {
+                break;
+            }
+            let mut below = vec![None; cols as usize];
+            for col in 0..cols as usize {
+                let set = sets[col];
+                if sets[..col].contains(&set) {
+                    continue;
+                }
+                let mut members: Vec<usize> =
+                    (col..cols as usize).filter(|&c| sets[c] == set).collect();
+                members.shuffle(rng);
+                let down = rng.gen_range(1..=members.len());
+                for &member in members.iter().take(down) {
+                    maze_grid.carve(row, member as Pos, Carve::Down);
+                    below[member] = Some(set);
+                }
+            }
+            for (col, set) in below.into_iter().enumerate() {
+                sets[col] = set.unwrap_or_else(|| {
+                    next_set += 1;
+                    next_set
+                });
+            }
+        }
+        maze_grid
+    }
+}
+
+// "Binary Tree" maze generation
+//
+// Every cell opens up or to the left. Quick and very regular: there's a
+// straight passage along the top row and the left column.
+pub struct BinaryTree;
+
+impl MazeGenerator for BinaryTree {
+    fn generate(&self, rows: Size, cols: Size, rng: &mut GameRng) -> MazeGrid {
+        let mut maze_grid = MazeGrid::walled(rows, cols);
+        for row in 0..rows {
+            for col in 0..cols {
+                let carve = match (row > 0, col > 0) {
+                    (true, true) if rng.gen_bool(0.5) => Carve::Up,
+                    (true, true) => Carve::Left,
+                    (true, false) => Carve::Up,
+                    (false, true) => Carve::Left,
+                    (false, false) => continue,
+                };
+                maze_grid.carve(row, col, carve);
+            }
+        }
+        maze_grid
+    }
+}
+
+// "Growing Tree" maze generation
+//
+// Keep a list of cells to grow the maze from, carve towards an unvisited
+// neighbor of either the newest one (like the backtracker) or a random one
+// (like Prim's) and drop cells that have run out of neighbors. Half and half
+// makes a mix of long passages and short dead ends.
+pub struct GrowingTree;
+
+impl MazeGenerator for GrowingTree {
+    fn generate(&self, rows: Size, cols: Size, rng: &mut GameRng) -> MazeGrid {
+        let mut maze_grid = MazeGrid::walled(rows, cols);
+        let mut visited = vec![false; (rows * cols) as usize];
+        let start = (rng.gen_range(0..rows), rng.gen_range(0..cols));
+        visited[(start.0 * cols + start.1) as usize] = true;
+        let mut cells = vec![start];
+        while !cells.is_empty() {
+            let index = if rng.gen_bool(0.5) {
+                cells.len() - 1
+            } else {
+                rng.gen_range(0..cells.len())
+            };
+            let (row, col) = cells[index];
+            match unvisited_neighbor(row, col, rows, cols, &visited, rng) {
+                Some((carve, next_row, next_col)) => {
+                    maze_grid.carve(row, col, carve);
+                    visited[(next_row * cols + next_col) as usize] = true;
+                    cells.push((next_row, next_col));
+                }
+                None => {
+                    cells.remove(index);
+                }
+            }
+        }
+        maze_grid
+    }
+}
+
+// "Rooms and Corridors" maze generation
+//
+// A backtracker maze with the walls inside a few random rectangles knocked
+// out, leaving open rooms joined by winding corridors.
+pub struct Rooms;
+
+impl MazeGenerator for Rooms {
+    fn generate(&self, rows: Size, cols: Size, rng: &mut GameRng) -> MazeGrid {
+        let mut maze_grid = Backtracker.generate(rows, cols, rng);
+        let rooms = (rows as usize * cols as usize / 24).max(1);
+        for _ in 0..rooms {
+            let height = rng.gen_range(2..=4).min(rows);
+            let width = rng.gen_range(2..=5).min(cols);
+            let top = rng.gen_range(0..=rows - height);
+            let left = rng.gen_range(0..=cols - width);
+            for row in top..top + height {
+                for col in left..left + width {
+                    if row > top {
+                        maze_grid.carve(row, col, Carve::Up);
+                    }
+                    if col > left {
+                        maze_grid.carve(row, col, Carve::Left);
+                    }
+                }
+            }
+        }
+        maze_grid
+    }
+}
+
+// a random neighbor of a cell that hasn't been visited yet (if any)
+fn unvisited_neighbor(
+    row: Pos,
+    col: Pos,
+    rows: Size,
+    cols: Size,
+    visited: &[bool],
+    rng: &mut GameRng,
+) -> Option<(Carve, Pos, Pos)> {
+    let mut carves = CARVES;
+    carves.shuffle(rng);
+    carves.into_iter().find_map(|carve| {
+        let (next_row, next_col) = carve.neighbor(row, col, rows, cols)?;
+        (!visited[(next_row * cols + next_col) as usize])
+            .then_some((carve, next_row, next_col))
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Carve {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Carve {
+    // neighboring maze cell (generated passages never wrap around)
+    fn neighbor(
+        self,
+        row: Pos,
+        col: Pos,
+        rows: Size,
+        cols: Size,
+    ) -> Option<(Pos, Pos)> {
+        match self {
+            Carve::Up if row > 0 => Some((row - 1, col)),
+            Carve::Down if row < rows - 1 => Some((row + 1, col)),
+            Carve::Left if col > 0 => Some((row, col - 1)),
+            Carve::Right if col < cols - 1 => Some((row, col + 1)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use std::collections::VecDeque;
+
+    const ROWS: Size = 12;
+    const COLS: Size = 17;
+
+    fn generate(algorithm: MazeAlgorithm, seed: u64) -> MazeGrid {
+        let mut rng = GameRng::seed_from_u64(seed);
+        algorithm.generator().generate(ROWS, COLS, &mut rng)
+    }
+
+    // passages between cells that are next to each other without wrapping
+    fn passages(maze_grid: &MazeGrid) -> Vec<((Pos, Pos), (Pos, Pos))> {
+        let mut passages = vec![];
+        for row in 0..ROWS {
+            for col in 0..COLS {
+                let walls = maze_grid.get(row, col);
+                if row > 0 && !walls.top {
+                    passages.push(((row - 1, col), (row, col)));
+                }
+                if col > 0 && !walls.left {
+                    passages.push(((row, col - 1), (row, col)));
+                }
+            }
+        }
+        passages
+    }
+
+    // top and left walls of every cell
+    fn walls(maze_grid: &MazeGrid) -> Vec<(bool, bool)> {
+        let mut walls = vec![];
+        for row in 0..ROWS {
+            for col in 0..COLS {
+                let cell = maze_grid.get(row, col);
+                walls.push((cell.top, cell.left));
+            }
+        }
+        walls
+    }
+
+    #[test]
+    fn every_cell_is_reachable() {
+        for &algorithm in MazeAlgorithm::value_variants() {
+            for seed in 0..10 {
+                let passages = passages(&generate(algorithm, seed));
+                let index =
+                    |(row, col): (Pos, Pos)| (row * COLS + col) as usize;
+                let mut visited = vec![false; (ROWS * COLS) as usize];
+                let mut queue = VecDeque::from([(0, 0)]);
+                visited[0] = true;
+                while let Some(cell) = queue.pop_front() {
+                    for &(a, b) in passages.iter() {
+                        let next = if a == cell {
+                            b
+                        } else if b == cell {
+                            a
+                        } else {
+                            continue;
+                        };
+                        if !visited[index(next)] {
+                            visited[index(next)] = true;
+                            queue.push_back(next);
+                        }
+                    }
+                }
+                assert!(
+                    visited.iter().all(|&visited| visited),
+                    "{} maze {seed} has unreachable cells",
+                    algorithm.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn perfect_mazes_are_trees() {
+        for &algorithm in MazeAlgorithm::value_variants() {
+            // rooms knock out extra walls on purpose
+            if algorithm == MazeAlgorithm::Rooms {
+                continue;
+            }
+            for seed in 0..10 {
+                let passages = passages(&generate(algorithm, seed));
+                assert_eq!(
+                    passages.len(),
+                    (ROWS * COLS) as usize - 1,
+                    "{} maze {seed} isn't a perfect maze",
+                    algorithm.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_maze() {
+        for &algorithm in MazeAlgorithm::value_variants() {
+            for seed in 0..10 {
+                assert_eq!(
+                    walls(&generate(algorithm, seed)),
+                    walls(&generate(algorithm, seed)),
+                    "{} maze {seed} changed",
+                    algorithm.name()
+                );
+            }
+        }
+    }
+}
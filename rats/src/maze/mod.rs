@@ -0,0 +1,372 @@
+use crate::game_context::GameRng;
+use rand::{distributions::Uniform, Rng};
+use std::cell::RefCell;
+use video::{
+    Buffer, Pos, Size, SizeWrapping, ATTR_NONE, MAZE_ACROSS, MAZE_BOTTOM,
+    MAZE_BOTTOM_LEFT, MAZE_BOTTOM_RIGHT, MAZE_BOTTOM_T, MAZE_CROSS, MAZE_DOWN,
+    MAZE_LEFT, MAZE_LEFT_T, MAZE_NONE, MAZE_RIGHT, MAZE_RIGHT_T, MAZE_TOP,
+    MAZE_TOP_LEFT, MAZE_TOP_RIGHT, MAZE_TOP_T, MAZE_WALLS_END,
+    MAZE_WALLS_START,
+};
+
+mod file;
+mod generators;
+
+pub use file::*;
+pub use generators::*;
+
+#[derive(Debug, Clone)]
+pub struct Maze {
+    // size in maze cells
+    cell_rows: Size,
+    cell_cols: Size,
+    // bounded mazes have a solid wall all the way around them instead of
+    // wrapping around, the buffer has an extra row and column for the bottom
+    // and right walls
+    bounded: bool,
+    pub buffer: Buffer,
+}
+
+thread_local! {
+    pub static PRISTINE_MAZE:RefCell<Maze> = RefCell::new(Maze::new(10,10,false));
+}
+
+pub fn with_pristine_maze<F, T>(action: F) -> T
+where
+    F: Fn(&Maze) -> T,
+{
+    PRISTINE_MAZE.with(|maze| action(&maze.borrow()))
+}
+
+// maze cell dimensions
+pub const MAZE_CELL_ROWS: Size = 9;
+pub const MAZE_CELL_COLS: Size = 16;
+
+impl Maze {
+    pub fn new(cell_rows: Size, cell_cols: Size, bounded: bool) -> Maze {
+        assert!(cell_cols >= 2 && cell_rows >= 2, "invalid maze dimensions");
+        let rows = (MAZE_CELL_ROWS + 1) * cell_rows + bounded as Size;
+        let cols = (MAZE_CELL_COLS + 1) * cell_cols + bounded as Size;
+        Maze {
+            cell_rows,
+            cell_cols,
+            bounded,
+            buffer: Buffer::new(rows, cols),
+        }
+    }
+
+    pub fn is_bounded(&self) -> bool {
+        self.bounded
+    }
+
+    pub fn cell_rows(&self) -> Size {
+        self.cell_rows
+    }
+
+    pub fn cell_cols(&self) -> Size {
+        self.cell_cols
+    }
+
+    pub fn rows(&self) -> Size {
+        self.buffer.rows
+    }
+
+    pub fn cols(&self) -> Size {
+        self.buffer.cols
+    }
+
+    pub fn is_wall(&self, row: Pos, col: Pos) -> bool {
+        let ch = self.buffer.get_char(row, col);
+        (MAZE_WALLS_START..=MAZE_WALLS_END).contains(&ch)
+    }
+
+    pub fn generate(
+        &mut self,
+        algorithm: MazeAlgorithm,
+        density: usize,
+        rng: &mut GameRng,
+    ) {
+        let mut maze_grid =
+            algorithm
+                .generator()
+                .generate(self.cell_rows, self.cell_cols, rng);
+        let distribution = Uniform::new_inclusive(1, 100);
+        for cell_row in 0..self.cell_rows {
+            for cell_col in 0..self.cell_cols {
+                if density == 0 || rng.sample(distribution) > density {
+                    maze_grid.clear(cell_row, cell_col);
+                }
+            }
+        }
+        self.draw(&maze_grid);
+    }
+
+    // replace the maze with the walls of a grid of the same size, bounded
+    // mazes also get the joiners and walls along the bottom and right edges
+    pub fn draw(&mut self, maze_grid: &MazeGrid) {
+        let maze_grid = if self.bounded {
+            maze_grid.bounded()
+        } else {
+            maze_grid.clone()
+        };
+        let edge = self.bounded as Size;
+        self.buffer.clear();
+        for cell_row in 0..self.cell_rows + edge {
+            let row = cell_row * (MAZE_CELL_ROWS + 1);
+            for cell_col in 0..self.cell_cols + edge {
+                let col = cell_col * (MAZE_CELL_COLS + 1);
+                let walls = maze_grid.get(cell_row, cell_col);
+                self.buffer.set_chattr(
+                    row,
+                    col,
+                    maze_grid.joiner(cell_row, cell_col),
+                    ATTR_NONE,
+                );
+                if walls.top {
+                    for col_index in 1..=MAZE_CELL_COLS {
+                        self.buffer.set_chattr(
+                            row,
+                            col + col_index,
+                            MAZE_ACROSS,
+                            ATTR_NONE,
+                        );
+                    }
+                }
+                if walls.left {
+                    for row_index in 1..=MAZE_CELL_ROWS {
+                        self.buffer.set_chattr(
+                            row + row_index,
+                            col,
+                            MAZE_DOWN,
+                            ATTR_NONE,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // the walls of every maze cell
+    pub fn grid(&self) -> MazeGrid {
+        let mut maze_grid = MazeGrid::walled(self.cell_rows, self.cell_cols);
+        maze_grid.bounded = self.bounded;
+        for cell_row in 0..self.cell_rows {
+            let row = cell_row * (MAZE_CELL_ROWS + 1);
+            for cell_col in 0..self.cell_cols {
+                let col = cell_col * (MAZE_CELL_COLS + 1);
+                maze_grid.set(
+                    cell_row,
+                    cell_col,
+                    Walls {
+                        top: self.is_wall(row, col + 1),
+                        left: self.is_wall(row + 1, col),
+                    },
+                );
+            }
+        }
+        maze_grid
+    }
+
+    // top left corner of a maze cell (the joiner)
+    pub fn cell_position(cell_row: Pos, cell_col: Pos) -> (Pos, Pos) {
+        (
+            cell_row * (MAZE_CELL_ROWS + 1),
+            cell_col * (MAZE_CELL_COLS + 1),
+        )
+    }
+
+    // maze cell containing a position
+    pub fn cell_at(row: Pos, col: Pos) -> (Pos, Pos) {
+        (row / (MAZE_CELL_ROWS + 1), col / (MAZE_CELL_COLS + 1))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Walls {
+    top: bool,
+    left: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct MazeGrid {
+    rows: Size,
+    cols: Size,
+    bounded: bool,
+    grid: Vec<Walls>,
+}
+
+// Each maze cell has a wall along its top and left sides. The maze wraps
+// around, so the top walls of the first row are also the bottom walls of the
+// last row and the left walls of the first column are also the right walls of
+// the last column. Bounded grids don't wrap around, they have an extra row
+// with nothing but a bottom wall and an extra column with nothing but a right
+// wall.
+impl MazeGrid {
+    pub fn rows(&self) -> Size {
+        self.rows
+    }
+
+    pub fn cols(&self) -> Size {
+        self.cols
+    }
+
+    pub fn get(&self, row: Pos, col: Pos) -> Walls {
+        if row < self.rows && col < self.cols {
+            self.grid[(row * self.cols + col) as usize].clone()
+        } else if self.bounded {
+            Walls {
+                top: row == self.rows && col < self.cols,
+                left: col == self.cols && row < self.rows,
+            }
+        } else {
+            Walls {
+                top: false,
+                left: false,
+            }
+        }
+    }
+
+    pub fn set(&mut self, row: Pos, col: Pos, walls: Walls) {
+        if row < self.rows && col < self.cols {
+            self.grid[(row * self.cols + col) as usize] = walls;
+        }
+    }
+
+    pub fn clear(&mut self, row: Pos, col: Pos) {
+        if row < self.rows && col < self.cols {
+            self.grid[(row * self.cols + col) as usize] = Walls {
+                top: false,
+                left: false,
+            };
+        }
+    }
+
+    // every cell surrounded by walls, which is where most maze generators
+    // start
+    fn walled(rows: Size, cols: Size) -> MazeGrid {
+        MazeGrid {
+            rows,
+            cols,
+            bounded: false,
+            grid: vec![
+                Walls {
+                    top: true,
+                    left: true
+                };
+                (rows * cols) as usize
+            ],
+        }
+    }
+
+    // the same walls plus the ones along the top and left edges, which
+    // become the outer wall of a bounded maze
+    fn bounded(&self) -> MazeGrid {
+        let mut maze_grid = self.clone();
+        maze_grid.bounded = true;
+        for col in 0..self.cols {
+            maze_grid.grid[col as usize].top = true;
+        }
+        for row in 0..self.rows {
+            maze_grid.grid[(row * self.cols) as usize].left = true;
+        }
+        maze_grid
+    }
+
+    // the cell whose top (true) or left (false) wall is the wall between a
+    // cell and its neighbor (wrapping around the edges of the maze)
+    fn wall(&self, row: Pos, col: Pos, side: Carve) -> (Pos, Pos, bool) {
+        match side {
+            Carve::Up => (row, col, true),
+            Carve::Down => ((row + 1) % self.rows, col, true),
+            Carve::Left => (row, col, false),
+            Carve::Right => (row, (col + 1) % self.cols, false),
+        }
+    }
+
+    // remove the wall between a cell and its neighbor
+    fn carve(&mut self, row: Pos, col: Pos, carve: Carve) {
+        let (row, col, top) = self.wall(row, col, carve);
+        let mut walls = self.get(row, col);
+        if top {
+            walls.top = false;
+        } else {
+            walls.left = false;
+        }
+        self.set(row, col, walls);
+    }
+
+    // add or remove the wall between a cell and its neighbor
+    pub fn toggle_wall(&mut self, row: Pos, col: Pos, side: Carve) {
+        let (row, col, top) = self.wall(row, col, side);
+        let mut walls = self.get(row, col);
+        if top {
+            walls.top = !walls.top;
+        } else {
+            walls.left = !walls.left;
+        }
+        self.set(row, col, walls);
+    }
+
+    // the character where the walls of a cell meet the walls of the cells
+    // above and to the left of it (there aren't any outside of a bounded
+    // grid)
+    fn joiner(&self, row: Pos, col: Pos) -> u8 {
+        let (row_1, col_1) = if self.bounded {
+            (row.checked_sub(1), col.checked_sub(1))
+        } else {
+            (Some(row.dec(self.rows)), Some(col.dec(self.cols)))
+        };
+        let mut wall_index = 0;
+        if row_1.is_some_and(|row_1| self.get(row_1, col).left) {
+            wall_index |= 0b_1000;
+        }
+        if self.get(row, col).top {
+            wall_index |= 0b_0100;
+        }
+        if self.get(row, col).left {
+            wall_index |= 0b_0010;
+        }
+        if col_1.is_some_and(|col_1| self.get(row, col_1).top) {
+            wall_index |= 0b_0001;
+        }
+        let wall_chars: [u8; 16] = [
+            /* 0000 */ MAZE_NONE,
+            /* 0001 */ MAZE_LEFT,
+            /* 0010 */ MAZE_BOTTOM,
+            /* 0011 */ MAZE_TOP_RIGHT,
+            /* 0100 */ MAZE_RIGHT,
+            /* 0101 */ MAZE_ACROSS,
+            /* 0110 */ MAZE_TOP_LEFT,
+            /* 0111 */ MAZE_TOP_T,
+            /* 1000 */ MAZE_TOP,
+            /* 1001 */ MAZE_BOTTOM_LEFT,
+            /* 1010 */ MAZE_DOWN,
+            /* 1011 */ MAZE_RIGHT_T,
+            /* 1100 */ MAZE_BOTTOM_RIGHT,
+            /* 1101 */ MAZE_BOTTOM_T,
+            /* 1110 */ MAZE_LEFT_T,
+            /* 1111 */ MAZE_CROSS,
+        ];
+        wall_chars[wall_index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels::MAX_MAZE_CELLS;
+
+    #[test]
+    fn largest_bounded_maze_fits_in_a_buffer() {
+        let mut maze = Maze::new(MAX_MAZE_CELLS, MAX_MAZE_CELLS, true);
+        maze.draw(&MazeGrid::walled(MAX_MAZE_CELLS, MAX_MAZE_CELLS));
+        let (rows, cols) = (maze.rows(), maze.cols());
+        assert_eq!(rows, (MAZE_CELL_ROWS + 1) * MAX_MAZE_CELLS + 1);
+        assert_eq!(cols, (MAZE_CELL_COLS + 1) * MAX_MAZE_CELLS + 1);
+        // the outer wall goes all the way around the bottom right corner
+        assert!(maze.is_wall(rows - 1, cols - 2));
+        assert!(maze.is_wall(rows - 2, cols - 1));
+        assert!(maze.is_wall(rows - 1, cols - 1));
+        assert!(!maze.is_wall(rows - 2, cols - 2));
+    }
+}
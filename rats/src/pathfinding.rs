@@ -0,0 +1,182 @@
+use crate::{
+    entities::{dir, Direction, Position},
+    maze::Maze,
+};
+use std::collections::VecDeque;
+use video::{Pos, Size, SizeWrapping};
+
+// Paths through the maze for anything that is 2x2 characters in size (rats
+// and the player). Brats are a single character, so they can follow the same
+// paths, but a brat can also be somewhere that no path goes through. Positions
+// refer to the top left corner of the entity and the maze wraps around in both
+// directions.
+
+const DIRECTIONS: [Direction; 4] = [dir::UP, dir::DOWN, dir::LEFT, dir::RIGHT];
+
+// true if a 2x2 entity fits at this position
+pub fn fits(maze: &Maze, pos: Position) -> bool {
+    let row2 = pos.row.inc(maze.rows());
+    let col2 = pos.col.inc(maze.cols());
+    !(maze.is_wall(pos.row, pos.col)
+        || maze.is_wall(pos.row, col2)
+        || maze.is_wall(row2, pos.col)
+        || maze.is_wall(row2, col2))
+}
+
+// next position in a (non-diagonal) direction
+fn step(pos: Position, dir: Direction, rows: Size, cols: Size) -> Position {
+    match dir {
+        dir::UP => Position {
+            row: pos.row.dec(rows),
+            col: pos.col,
+        },
+        dir::DOWN => Position {
+            row: pos.row.inc(rows),
+            col: pos.col,
+        },
+        dir::LEFT => Position {
+            row: pos.row,
+            col: pos.col.dec(cols),
+        },
+        _ => Position {
+            row: pos.row,
+            col: pos.col.inc(cols),
+        },
+    }
+}
+
+// Number of steps from every position within range to the closest origin (the
+// players) found with a breadth first search. It only has to be recomputed
+// when a player moves and then any number of rats and brats can find their
+// way to the closest player by moving to a neighboring position that is
+// closer.
+pub struct FlowField {
+    origins: Option<Vec<Position>>,
+    rows: Size,
+    cols: Size,
+    distances: Vec<u32>,
+    // positions reached by the last search, so that they can be cleared
+    // without going over the whole maze
+    reached: Vec<usize>,
+}
+
+const UNREACHED: u32 = u32::MAX;
+
+impl FlowField {
+    pub fn new() -> FlowField {
+        FlowField {
+            origins: None,
+            rows: 0,
+            cols: 0,
+            distances: vec![],
+            reached: vec![],
+        }
+    }
+
+    // forget the current field (e.g. when the maze changes)
+    pub fn reset(&mut self) {
+        self.origins = None;
+    }
+
+    // recompute distances to the origins unless that's where they already
+    // lead, positions more than range steps away are left unreached
+    pub fn update(&mut self, maze: &Maze, origins: &[Position], range: u32) {
+        if self.origins.as_deref() == Some(origins) {
+            return;
+        }
+        if self.rows != maze.rows() || self.cols != maze.cols() {
+            self.rows = maze.rows();
+            self.cols = maze.cols();
+            self.distances =
+                vec![UNREACHED; self.rows as usize * self.cols as usize];
+            self.reached.clear();
+        }
+        for index in self.reached.drain(..) {
+            self.distances[index] = UNREACHED;
+        }
+        self.origins = Some(origins.to_vec());
+        let mut queue = VecDeque::new();
+        for &origin in origins {
+            if fits(maze, origin) && self.distance(origin).is_none() {
+                self.reach(origin, 0);
+                queue.push_back((origin, 0));
+            }
+        }
+        while let Some((pos, distance)) = queue.pop_front() {
+            if distance == range {
+                continue;
+            }
+            for dir in DIRECTIONS {
+                let next = step(pos, dir, self.rows, self.cols);
+                if self.distance(next).is_none() && fits(maze, next) {
+                    self.reach(next, distance + 1);
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+    }
+
+    fn reach(&mut self, pos: Position, distance: u32) {
+        let index = self.index(pos);
+        self.distances[index] = distance;
+        self.reached.push(index);
+    }
+
+    fn index(&self, pos: Position) -> usize {
+        pos.row as usize * self.cols as usize + pos.col as usize
+    }
+
+    // number of steps to the closest origin (if within range)
+    pub fn distance(&self, pos: Position) -> Option<u32> {
+        if pos.row >= self.rows || pos.col >= self.cols {
+            return None;
+        }
+        match self.distances[self.index(pos)] {
+            UNREACHED => None,
+            distance => Some(distance),
+        }
+    }
+
+    // every position reached by the last search, closest first
+    pub fn positions(&self) -> Vec<Position> {
+        let cols = self.cols as usize;
+        self.reached
+            .iter()
+            .map(|&index| Position {
+                row: (index / cols) as Pos,
+                col: (index % cols) as Pos,
+            })
+            .collect()
+    }
+
+    // direction of a step that leads closer to an origin
+    pub fn direction(&self, pos: Position) -> Option<Direction> {
+        let mut best = (self.distance(pos)?, dir::NONE);
+        for dir in DIRECTIONS {
+            let next = step(pos, dir, self.rows, self.cols);
+            if let Some(distance) = self.distance(next) {
+                if distance < best.0 {
+                    best = (distance, dir);
+                }
+            }
+        }
+        match best {
+            (_, dir::NONE) => None,
+            (_, dir) => Some(dir),
+        }
+    }
+}
+
+// every position a 2x2 entity can get to from the origins, no matter how far
+// away
+pub fn reachable(maze: &Maze, origins: &[Position]) -> FlowField {
+    let mut field = FlowField::new();
+    field.update(maze, origins, u32::MAX);
+    field
+}
+
+impl Default for FlowField {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,370 @@
+use crate::{
+    config::Config,
+    errors::{replay_error, Result},
+    input::{Command, KeyMap},
+    levels::Difficulty,
+    maze::{MazeAlgorithm, MazeFile},
+};
+use clap::ValueEnum;
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+use video::{Keycode, Size};
+
+const REPLAY_HEADER: &str = "rats replay 2";
+
+// version 1 recorded keys instead of commands, they're translated using the
+// default key map
+const REPLAY_HEADER_V1: &str = "rats replay 1";
+
+// A replay file is a plain text file which starts with everything needed to
+// recreate the same game (seed, maze and game options, length of a simulation
+// tick, tuning settings) followed by every command along with the tick it
+// occurred on. A maze loaded from a maze file is copied into the replay
+// (after a maze-file line) so that the replay doesn't depend on the maze file
+// staying the same:
+//
+//   rats replay 2
+//   seed 1234
+//   tick 16
+//   maze 15 15
+//   density 75
+//   players 2
+//   difficulty normal
+//   algorithm prims
+//   bounded
+//   maze-file
+//   rats maze 1
+//   size 15 15
+//   ...
+//   damage 50 25
+//   config rat_kill 50
+//   ...
+//   down 120 move_up
+//   up 131 move_up
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayEvent {
+    pub tick: u64,
+    pub down: bool,
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Replay {
+    pub seed: u64,
+    pub tick_ms: u32,
+    pub maze_height: Size,
+    pub maze_width: Size,
+    pub density: Option<usize>,
+    pub factories: Option<usize>,
+    pub classic: bool,
+    pub players: usize,
+    pub difficulty: Difficulty,
+    pub algorithm: MazeAlgorithm,
+    pub bounded: bool,
+    pub maze_file: Option<MazeFile>,
+    pub rat_damage: usize,
+    pub brat_damage: usize,
+    pub config: Config,
+    pub events: VecDeque<ReplayEvent>,
+}
+
+impl Replay {
+    pub fn record(&mut self, tick: u64, down: bool, command: Command) {
+        self.events.push_back(ReplayEvent {
+            tick,
+            down,
+            command,
+        });
+    }
+
+    // next recorded event that is due on or before this tick (if any)
+    pub fn next_event(&mut self, tick: u64) -> Option<ReplayEvent> {
+        if self.events.front()?.tick <= tick {
+            self.events.pop_front()
+        } else {
+            None
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "{REPLAY_HEADER}")?;
+        writeln!(file, "seed {}", self.seed)?;
+        writeln!(file, "tick {}", self.tick_ms)?;
+        writeln!(file, "maze {} {}", self.maze_height, self.maze_width)?;
+        if let Some(density) = self.density {
+            writeln!(file, "density {density}")?;
+        }
+        if let Some(factories) = self.factories {
+            writeln!(file, "factories {factories}")?;
+        }
+        if self.classic {
+            writeln!(file, "classic")?;
+        }
+        if self.players > 1 {
+            writeln!(file, "players {}", self.players)?;
+        }
+        writeln!(file, "difficulty {}", self.difficulty.name())?;
+        if self.algorithm != MazeAlgorithm::default() {
+            writeln!(file, "algorithm {}", self.algorithm.name())?;
+        }
+        if self.bounded {
+            writeln!(file, "bounded")?;
+        }
+        if let Some(maze_file) = &self.maze_file {
+            writeln!(file, "maze-file")?;
+            maze_file.write(&mut file)?;
+        }
+        writeln!(file, "damage {} {}", self.rat_damage, self.brat_damage)?;
+        for (key, value) in self.config.settings() {
+            writeln!(file, "config {key} {value}")?;
+        }
+        for event in self.events.iter() {
+            writeln!(
+                file,
+                "{} {} {}",
+                if event.down { "down" } else { "up" },
+                event.tick,
+                event.command.name()
+            )?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Replay> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let keymap = match lines.next() {
+            Some(Ok(line)) if line == REPLAY_HEADER => None,
+            Some(Ok(line)) if line == REPLAY_HEADER_V1 => {
+                Some(KeyMap::default())
+            }
+            _ => return Err(replay_error("not a replay file")),
+        };
+        let mut replay = Replay {
+            players: 1,
+            ..Replay::default()
+        };
+        // the seed and damage don't have defaults that would give the same
+        // game, so they have to be there
+        let mut seed = None;
+        let mut damage = None;
+        while let Some(line) = lines.next() {
+            let line = line?;
+            if line == "maze-file" {
+                replay.maze_file = Some(MazeFile::read(&mut lines)?);
+                continue;
+            }
+            // older replays only have the path of the maze file (which may
+            // contain spaces)
+            if let Some(path) = line.strip_prefix("maze-file ") {
+                replay.maze_file = Some(MazeFile::load(path)?);
+                continue;
+            }
+            // (version 1) key names may contain spaces so they always come last
+            let fields: Vec<&str> = line.splitn(3, ' ').collect();
+            match fields[..] {
+                ["seed", value] => seed = Some(parse(value)?),
+                ["tick", tick_ms] => replay.tick_ms = parse(tick_ms)?,
+                ["maze", height, width] => {
+                    replay.maze_height = parse(height)?;
+                    replay.maze_width = parse(width)?;
+                }
+                ["density", density] => replay.density = Some(parse(density)?),
+                ["factories", factories] => {
+                    replay.factories = Some(parse(factories)?)
+                }
+                ["classic"] => replay.classic = true,
+                ["players", players] => replay.players = parse(players)?,
+                ["difficulty", name] => {
+                    replay.difficulty = Difficulty::from_str(name, true)
+                        .map_err(replay_error)?
+                }
+                ["algorithm", name] => {
+                    replay.algorithm = MazeAlgorithm::from_str(name, true)
+                        .map_err(replay_error)?
+                }
+                ["bounded"] => replay.bounded = true,
+                ["damage", rat_damage, brat_damage] => {
+                    damage = Some((parse(rat_damage)?, parse(brat_damage)?))
+                }
+                ["config", key, value] => replay.config.set(key, value)?,
+                [kind @ ("down" | "up"), tick, name] => {
+                    let command = match &keymap {
+                        Some(keymap) => {
+                            let keycode =
+                                Keycode::from_name(name).ok_or_else(|| {
+                                    replay_error(format!("unknown key: {name}"))
+                                })?;
+                            // keys that didn't do anything
+                            let Some(command) = keymap.command(keycode) else {
+                                continue;
+                            };
+                            command
+                        }
+                        None => Command::from_name(name).ok_or_else(|| {
+                            replay_error(format!("unknown command: {name}"))
+                        })?,
+                    };
+                    replay.record(parse(tick)?, kind == "down", command);
+                }
+                [""] => {}
+                _ => return Err(replay_error(format!("invalid line: {line}"))),
+            }
+        }
+        let (Some(seed), Some((rat_damage, brat_damage))) = (seed, damage)
+        else {
+            return Err(replay_error("missing game parameters"));
+        };
+        if replay.tick_ms == 0
+            || replay.maze_height < 2
+            || replay.maze_width < 2
+        {
+            return Err(replay_error("missing game parameters"));
+        }
+        replay.seed = seed;
+        replay.rat_damage = rat_damage;
+        replay.brat_damage = brat_damage;
+        Ok(replay)
+    }
+}
+
+fn parse<T: std::str::FromStr>(field: &str) -> Result<T> {
+    field
+        .parse()
+        .map_err(|_| replay_error(format!("invalid value: {field}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::dir;
+    use std::{fs, path::PathBuf};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("rats-replay-{}-{name}", std::process::id()))
+    }
+
+    fn load_str(name: &str, contents: &str) -> Result<Replay> {
+        let path = temp_path(name);
+        fs::write(&path, contents)?;
+        let replay = Replay::load(&path);
+        fs::remove_file(&path)?;
+        replay
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut config = Config::default();
+        config.set("rat_kill", "7").unwrap();
+        let mut replay = Replay {
+            seed: 1234,
+            tick_ms: 16,
+            maze_height: 15,
+            maze_width: 20,
+            density: Some(75),
+            factories: None,
+            classic: false,
+            players: 2,
+            difficulty: Difficulty::Hard,
+            algorithm: MazeAlgorithm::Prims,
+            bounded: true,
+            maze_file: None,
+            rat_damage: 50,
+            brat_damage: 25,
+            config,
+            events: VecDeque::new(),
+        };
+        replay.record(3, true, Command::Move(0, dir::UP));
+        replay.record(9, false, Command::Move(0, dir::UP));
+        replay.record(12, true, Command::Fire(1, dir::LEFT));
+
+        let path = temp_path("save_and_load");
+        replay.save(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        let mut loaded = Replay::load(&path).unwrap();
+        loaded.save(&path).unwrap();
+        let resaved = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(saved, resaved);
+        assert_eq!(loaded.seed, 1234);
+        assert_eq!(loaded.tick_ms, 16);
+        assert_eq!((loaded.maze_height, loaded.maze_width), (15, 20));
+        assert_eq!(loaded.density, Some(75));
+        assert_eq!(loaded.factories, None);
+        assert_eq!(loaded.players, 2);
+        assert_eq!(loaded.difficulty, Difficulty::Hard);
+        assert_eq!(loaded.algorithm, MazeAlgorithm::Prims);
+        assert!(loaded.bounded);
+        assert_eq!((loaded.rat_damage, loaded.brat_damage), (50, 25));
+        assert_eq!(loaded.config, config);
+        assert_eq!(loaded.events.len(), 3);
+        let event = loaded.next_event(12).unwrap();
+        assert_eq!((event.tick, event.down), (3, true));
+        assert_eq!(event.command, Command::Move(0, dir::UP));
+        assert!(loaded.next_event(8).is_none());
+    }
+
+    #[test]
+    fn seed_and_damage_are_required() {
+        let game = "rats replay 2\ntick 16\nmaze 10 10\n";
+        assert!(load_str("complete", &format!("{game}seed 1\ndamage 5 5\n"))
+            .is_ok());
+        assert!(load_str("no_seed", &format!("{game}damage 5 5\n")).is_err());
+        assert!(load_str("no_damage", &format!("{game}seed 1\n")).is_err());
+    }
+
+    #[test]
+    fn version_1_keys_become_commands() {
+        let mut replay = load_str(
+            "version_1",
+            "rats replay 1\nseed 1\ntick 16\nmaze 10 10\ndamage 5 5\n\
+            down 4 Up\ndown 5 Keypad 8\nup 6 Space\n",
+        )
+        .unwrap();
+        // keys that aren't in the default key map are left out
+        assert_eq!(replay.events.len(), 2);
+        let event = replay.next_event(4).unwrap();
+        assert_eq!(event.command, Command::Move(0, dir::UP));
+        let event = replay.next_event(6).unwrap();
+        assert_eq!((event.tick, event.down), (6, false));
+        assert_eq!(event.command, Command::Pause);
+    }
+
+    #[test]
+    fn maze_is_part_of_the_replay() {
+        let mut maze_file = MazeFile::new(3, 4);
+        maze_file.factories.push((2, 3));
+        maze_file.starts[0] = Some((1, 1));
+        let mut replay = Replay {
+            seed: 1,
+            tick_ms: 16,
+            maze_height: 3,
+            maze_width: 4,
+            players: 1,
+            maze_file: Some(maze_file),
+            rat_damage: 5,
+            brat_damage: 5,
+            ..Replay::default()
+        };
+        replay.record(3, true, Command::Pause);
+
+        let path = temp_path("maze_is_part_of_the_replay");
+        replay.save(&path).unwrap();
+        let mut loaded = Replay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let maze_file = loaded.maze_file.take().unwrap();
+        assert_eq!((maze_file.rows(), maze_file.cols()), (3, 4));
+        assert_eq!(maze_file.factories, [(2, 3)]);
+        assert_eq!(maze_file.starts[0], Some((1, 1)));
+        assert_eq!(loaded.rat_damage, 5);
+        assert_eq!(loaded.events.len(), 1);
+    }
+}
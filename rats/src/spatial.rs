@@ -0,0 +1,142 @@
+use crate::entities::{EntityId, EntityList, Position};
+use std::collections::HashSet;
+use video::{Pos, Size, SizeWrapping};
+
+const EMPTY: u32 = u32::MAX;
+
+struct Entry {
+    entity: EntityId,
+    // position in the maze (row * cols + col)
+    cell: usize,
+    // next entry for the same position (or EMPTY)
+    next: u32,
+}
+
+// Which entities cover each character position of the maze. Brats and bullets
+// are a single character, everything else takes up 2x2 characters.
+//
+// The index has to be rebuilt whenever entities move. Entities added since the
+// last rebuild (e.g. new bullets) can't be found until the next one and ids
+// of entities removed since then won't be found in the entity list.
+pub struct SpatialIndex {
+    rows: Size,
+    cols: Size,
+    // first entry for every position in the maze (or EMPTY)
+    heads: Vec<u32>,
+    entries: Vec<Entry>,
+}
+
+impl SpatialIndex {
+    pub fn new() -> SpatialIndex {
+        SpatialIndex {
+            rows: 0,
+            cols: 0,
+            heads: vec![],
+            entries: vec![],
+        }
+    }
+
+    pub fn rebuild(&mut self, rows: Size, cols: Size, entities: &EntityList) {
+        if self.rows != rows || self.cols != cols {
+            self.rows = rows;
+            self.cols = cols;
+            self.heads = vec![EMPTY; rows as usize * cols as usize];
+        } else {
+            for entry in self.entries.iter() {
+                self.heads[entry.cell] = EMPTY;
+            }
+        }
+        self.entries.clear();
+        for (id, entity) in entities.iter() {
+            let (pos, size) = entity.footprint();
+            let mut row = pos.row;
+            for _ in 0..size {
+                let mut col = pos.col;
+                for _ in 0..size {
+                    self.insert(id, Position { row, col });
+                    col = col.inc(cols);
+                }
+                row = row.inc(rows);
+            }
+        }
+    }
+
+    fn insert(&mut self, entity: EntityId, pos: Position) {
+        if let Some(cell) = self.cell(pos) {
+            self.entries.push(Entry {
+                entity,
+                cell,
+                next: self.heads[cell],
+            });
+            self.heads[cell] = (self.entries.len() - 1) as u32;
+        }
+    }
+
+    fn cell(&self, pos: Position) -> Option<usize> {
+        if pos.row < self.rows && pos.col < self.cols {
+            Some(pos.row as usize * self.cols as usize + pos.col as usize)
+        } else {
+            None
+        }
+    }
+
+    // entities covering a position
+    pub fn at(&self, pos: Position) -> impl Iterator<Item = EntityId> + '_ {
+        let mut next = match self.cell(pos) {
+            Some(cell) => self.heads[cell],
+            None => EMPTY,
+        };
+        std::iter::from_fn(move || {
+            let entry = self.entries.get(next as usize)?;
+            next = entry.next;
+            Some(entry.entity)
+        })
+    }
+
+    // true if an entity covers any part of a size x size area
+    pub fn touches(&self, pos: Position, size: Size, entity: EntityId) -> bool {
+        let mut row = pos.row;
+        for _ in 0..size {
+            let mut col = pos.col;
+            for _ in 0..size {
+                if self.at(Position { row, col }).any(|other| other == entity) {
+                    return true;
+                }
+                col = col.inc(self.cols);
+            }
+            row = row.inc(self.rows);
+        }
+        false
+    }
+
+    // entities covering any position up to distance rows and columns away
+    // from center, each entity is only listed once
+    pub fn near(&self, center: Position, distance: Size) -> Vec<EntityId> {
+        let mut near = vec![];
+        if self.rows == 0 || self.cols == 0 {
+            return near;
+        }
+        let start = |pos: Pos, size: Size| {
+            (pos as i32 - distance as i32).rem_euclid(size as i32) as Pos
+        };
+        let span = |size: Size| (2 * distance as usize + 1).min(size as usize);
+        let mut row = start(center.row, self.rows);
+        for _ in 0..span(self.rows) {
+            let mut col = start(center.col, self.cols);
+            for _ in 0..span(self.cols) {
+                near.extend(self.at(Position { row, col }));
+                col = col.inc(self.cols);
+            }
+            row = row.inc(self.rows);
+        }
+        let mut seen = HashSet::new();
+        near.retain(|id| seen.insert(*id));
+        near
+    }
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
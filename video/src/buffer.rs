@@ -1,4 +1,7 @@
-use crate::{Size, SizeWrapping};
+use crate::{
+    Size, SizeWrapping, MAZE_ACROSS, MAZE_BOTTOM_LEFT, MAZE_BOTTOM_RIGHT,
+    MAZE_DOWN, MAZE_TOP_LEFT, MAZE_TOP_RIGHT,
+};
 
 #[derive(Clone)]
 pub struct Buffer {
@@ -24,13 +27,43 @@ pub const ATTR_DIM: u8 = 0x02;
 pub const ATTR_MASK: u8 = 0x03;
 pub const ATTR_COMBOS: usize = 4;
 
+// a rectangular area of a buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    pub row: Size,
+    pub col: Size,
+    pub rows: Size,
+    pub cols: Size,
+}
+
+impl Viewport {
+    pub fn new(row: Size, col: Size, rows: Size, cols: Size) -> Viewport {
+        Viewport {
+            row,
+            col,
+            rows,
+            cols,
+        }
+    }
+
+    // the area inside of a one character border
+    pub fn inner(&self) -> Viewport {
+        Viewport {
+            row: self.row + 1,
+            col: self.col + 1,
+            rows: self.rows.saturating_sub(2),
+            cols: self.cols.saturating_sub(2),
+        }
+    }
+}
+
 impl Buffer {
     pub fn new(rows: Size, cols: Size) -> Buffer {
         let mut buffer = Buffer {
             rows,
             cols,
-            characters: vec![0; (rows * cols) as usize],
-            attributes: vec![0; (rows * cols) as usize],
+            characters: vec![0; rows as usize * cols as usize],
+            attributes: vec![0; rows as usize * cols as usize],
         };
         buffer.clear();
         buffer
@@ -43,13 +76,14 @@ impl Buffer {
 
     pub fn set_char(&mut self, row: Size, col: Size, ch: u8) {
         if row < self.rows && col < self.cols {
-            self.characters[(row * self.cols + col) as usize] = ch;
+            let index = self.index(row, col);
+            self.characters[index] = ch;
         }
     }
 
     pub fn get_char(&self, row: Size, col: Size) -> u8 {
         if row < self.rows && col < self.cols {
-            self.characters[(row * self.cols + col) as usize]
+            self.characters[self.index(row, col)]
         } else {
             0
         }
@@ -57,14 +91,14 @@ impl Buffer {
 
     pub fn set_attr(&mut self, row: Size, col: Size, attr: u8) {
         if row < self.rows && col < self.cols {
-            self.attributes[(row * self.cols + col) as usize] =
-                attr & ATTR_MASK;
+            let index = self.index(row, col);
+            self.attributes[index] = attr & ATTR_MASK;
         }
     }
 
     pub fn get_attr(&self, row: Size, col: Size) -> u8 {
         if row < self.rows && col < self.cols {
-            self.attributes[(row * self.cols + col) as usize]
+            self.attributes[self.index(row, col)]
         } else {
             0
         }
@@ -87,16 +121,22 @@ impl Buffer {
         }
     }
 
+    // in usize, a large maze has more characters than fit in a Size
+    fn index(&self, row: Size, col: Size) -> usize {
+        row as usize * self.cols as usize + col as usize
+    }
+
     fn set_ca(&mut self, row: Size, col: Size, ch: u8, attr: u8) {
-        self.characters[(row * self.cols + col) as usize] = ch;
-        self.attributes[(row * self.cols + col) as usize] = attr;
+        let index = self.index(row, col);
+        self.characters[index] = ch;
+        self.attributes[index] = attr;
     }
 
     pub fn get_chattr(&self, row: Size, col: Size) -> (u8, u8) {
         if row < self.rows && col < self.cols {
             (
-                self.characters[(row * self.cols + col) as usize],
-                self.attributes[(row * self.cols + col) as usize],
+                self.characters[self.index(row, col)],
+                self.attributes[self.index(row, col)],
             )
         } else {
             (0, 0)
@@ -122,14 +162,43 @@ impl Buffer {
 
     pub fn copy_buffer(
         &self,
-        mut src_row: Size, // starting row in source buffer
-        src_col: Size,     // starting column in source buffer
-        dst: &mut Buffer,  // destination buffer
-        dst_row: Size,     // first row in destination buffer
+        src_row: Size,    // starting row in source buffer
+        src_col: Size,    // starting column in source buffer
+        dst: &mut Buffer, // destination buffer
+        dst_row: Size,    // first row in destination buffer
     ) {
-        for dst_row in dst_row..dst.rows {
+        let viewport = Viewport::new(
+            dst_row,
+            0,
+            dst.rows.saturating_sub(dst_row),
+            dst.cols,
+        );
+        self.copy_viewport(src_row, src_col, dst, viewport, None);
+    }
+
+    // Copy the area of this buffer starting at src_row, src_col into a
+    // viewport of another buffer. The source area wraps around the edges of
+    // this buffer and anything outside of the destination buffer is clipped.
+    // With a border attribute, a border is drawn around the edge of the
+    // viewport and the source area only fills the inside.
+    pub fn copy_viewport(
+        &self,
+        mut src_row: Size,
+        src_col: Size,
+        dst: &mut Buffer,
+        viewport: Viewport,
+        border: Option<u8>,
+    ) {
+        let area = match border {
+            Some(attr) => {
+                dst.draw_border(viewport, attr);
+                viewport.inner()
+            }
+            None => viewport,
+        };
+        for dst_row in area.row..area.row.saturating_add(area.rows) {
             let mut col = src_col;
-            for dst_col in 0..dst.cols {
+            for dst_col in area.col..area.col.saturating_add(area.cols) {
                 dst.set_char(dst_row, dst_col, self.get_char(src_row, col));
                 dst.set_attr(dst_row, dst_col, self.get_attr(src_row, col));
                 col = col.inc(self.cols);
@@ -138,6 +207,29 @@ impl Buffer {
         }
     }
 
+    // a box around the edge of a viewport (drawn with maze wall characters)
+    pub fn draw_border(&mut self, viewport: Viewport, attr: u8) {
+        if viewport.rows < 2 || viewport.cols < 2 {
+            return;
+        }
+        let top = viewport.row;
+        let bottom = viewport.row + viewport.rows - 1;
+        let left = viewport.col;
+        let right = viewport.col + viewport.cols - 1;
+        for col in left + 1..right {
+            self.set_chattr(top, col, MAZE_ACROSS, attr);
+            self.set_chattr(bottom, col, MAZE_ACROSS, attr);
+        }
+        for row in top + 1..bottom {
+            self.set_chattr(row, left, MAZE_DOWN, attr);
+            self.set_chattr(row, right, MAZE_DOWN, attr);
+        }
+        self.set_chattr(top, left, MAZE_TOP_LEFT, attr);
+        self.set_chattr(top, right, MAZE_TOP_RIGHT, attr);
+        self.set_chattr(bottom, left, MAZE_BOTTOM_LEFT, attr);
+        self.set_chattr(bottom, right, MAZE_BOTTOM_RIGHT, attr);
+    }
+
     pub fn copy_to(&self, dst: &mut Buffer) {
         assert!(self.rows == dst.rows && self.cols == dst.cols);
         dst.characters.copy_from_slice(&self.characters);
@@ -1,13 +1,13 @@
 use crate::{
-    buffer::{ATTR_DIM, ATTR_NONE, ATTR_REVERSE},
+    buffer::{ATTR_DIM, ATTR_REVERSE},
     charmaps::{ASCII, ASCII_START, MAZE_WALLS, MAZE_WALLS_START},
     errors::sdl_error,
     Result, Video, ATTR_COMBOS, BIG_BLANK, BIG_BLANK_START, BIG_BOOMS,
     BIG_BOOM_START, BRATS, BRATS_START, BULLETS, BULLETS_START,
     BYTES_PER_PIXEL, CHAR_CELL_HEIGHT, CHAR_CELL_WIDTH, CRT_BACKGROUND,
     CRT_GREEN, CYAN, DARK_GREEN, EMPTY_CHAR_CELL, FACTORIES, FACTORIES_START,
-    FONT_SIZE, LIL_BOOMS, LIL_BOOM_START, PLAYER, PLAYER_START, RATS,
-    RATS_START, RED, WHITE, YELLOW,
+    FONT_SIZE, LIL_BOOMS, LIL_BOOM_START, MINIMAP, MINIMAP_START, PLAYER,
+    PLAYER_START, RATS, RATS_START, RED, WHITE, YELLOW,
 };
 use sdl2::render::Texture;
 
@@ -19,125 +19,102 @@ impl Video {
         color: bool,
     ) -> Result<()> {
         assert!(textures.len() == ATTR_COMBOS);
-        clear_charmap_textures(textures, scale)?;
-        charmap_textures(textures, scale, &ASCII, ASCII_START, CRT_GREEN)?;
-        charmap_textures(
-            textures,
-            scale,
-            &MAZE_WALLS,
-            MAZE_WALLS_START,
-            if color { WHITE } else { CRT_GREEN },
-        )?;
-        charmap_textures(
-            textures,
-            scale,
-            &BULLETS,
-            BULLETS_START,
-            if color { YELLOW } else { CRT_GREEN },
-        )?;
-        charmap_textures(textures, scale, &BRATS, BRATS_START, CRT_GREEN)?;
-        charmap_textures(
-            textures,
-            scale,
-            &LIL_BOOMS,
-            LIL_BOOM_START,
-            if color { RED } else { CRT_GREEN },
-        )?;
-        wide_charmap_textures(
-            textures,
-            scale,
-            &FACTORIES,
-            FACTORIES_START,
-            if color { DARK_GREEN } else { CRT_GREEN },
-        )?;
-        wide_charmap_textures(
-            textures,
-            scale,
-            &PLAYER,
-            PLAYER_START,
-            if color { CYAN } else { CRT_GREEN },
-        )?;
-        wide_charmap_textures(textures, scale, &RATS, RATS_START, CRT_GREEN)?;
-        wide_charmap_textures(
-            textures,
-            scale,
-            &BIG_BOOMS,
-            BIG_BOOM_START,
-            if color { RED } else { CRT_GREEN },
-        )?;
-        wide_charmap_textures(
-            textures,
-            scale,
-            &BIG_BLANK,
-            BIG_BLANK_START,
-            CRT_GREEN,
-        )?;
+        let fonts = charmap_pixels(scale, color);
+        for (texture, font) in textures.iter_mut().zip(fonts.iter()) {
+            texture
+                .with_lock(None, |pixels, _pitch| pixels.copy_from_slice(font))
+                .map_err(sdl_error)?;
+        }
         Ok(())
     }
 }
 
-fn clear_charmap_textures(
-    textures: &mut [Texture],
-    scale: usize,
-) -> Result<()> {
-    textures[ATTR_NONE as usize]
-        .with_lock(None, |pixels, _pitch| {
-            set_pixels_empty(pixels, scale);
-        })
-        .map_err(sdl_error)?;
-    textures[ATTR_REVERSE as usize]
-        .with_lock(None, |pixels, _pitch| {
-            set_pixels_empty(pixels, scale);
-        })
-        .map_err(sdl_error)?;
-    textures[ATTR_DIM as usize]
-        .with_lock(None, |pixels, _pitch| {
-            set_pixels_empty(pixels, scale);
-        })
-        .map_err(sdl_error)?;
-    textures[(ATTR_REVERSE | ATTR_DIM) as usize]
-        .with_lock(None, |pixels, _pitch| {
-            set_pixels_empty(pixels, scale);
-        })
-        .map_err(sdl_error)?;
-    Ok(())
+// RGB pixels of the whole character set for every attribute combination, each
+// one is a column of FONT_SIZE character cells at the given scale
+pub fn charmap_pixels(scale: usize, color: bool) -> Vec<Vec<u8>> {
+    let mut fonts = vec![
+        vec![
+            0;
+            FONT_SIZE as usize
+                * BYTES_PER_PIXEL
+                * (CHAR_CELL_WIDTH * scale)
+                * (CHAR_CELL_HEIGHT * scale)
+        ];
+        ATTR_COMBOS
+    ];
+    clear_fonts(&mut fonts, scale);
+    add_chars(&mut fonts, scale, &ASCII, ASCII_START, CRT_GREEN);
+    add_chars(
+        &mut fonts,
+        scale,
+        &MAZE_WALLS,
+        MAZE_WALLS_START,
+        if color { WHITE } else { CRT_GREEN },
+    );
+    add_chars(
+        &mut fonts,
+        scale,
+        &BULLETS,
+        BULLETS_START,
+        if color { YELLOW } else { CRT_GREEN },
+    );
+    add_chars(&mut fonts, scale, &BRATS, BRATS_START, CRT_GREEN);
+    add_chars(
+        &mut fonts,
+        scale,
+        &MINIMAP,
+        MINIMAP_START,
+        if color { WHITE } else { CRT_GREEN },
+    );
+    add_chars(
+        &mut fonts,
+        scale,
+        &LIL_BOOMS,
+        LIL_BOOM_START,
+        if color { RED } else { CRT_GREEN },
+    );
+    add_wide_chars(
+        &mut fonts,
+        scale,
+        &FACTORIES,
+        FACTORIES_START,
+        if color { DARK_GREEN } else { CRT_GREEN },
+    );
+    add_wide_chars(
+        &mut fonts,
+        scale,
+        &PLAYER,
+        PLAYER_START,
+        if color { CYAN } else { CRT_GREEN },
+    );
+    add_wide_chars(&mut fonts, scale, &RATS, RATS_START, CRT_GREEN);
+    add_wide_chars(
+        &mut fonts,
+        scale,
+        &BIG_BOOMS,
+        BIG_BOOM_START,
+        if color { RED } else { CRT_GREEN },
+    );
+    add_wide_chars(&mut fonts, scale, &BIG_BLANK, BIG_BLANK_START, CRT_GREEN);
+    fonts
+}
+
+fn clear_fonts(fonts: &mut [Vec<u8>], scale: usize) {
+    for font in fonts.iter_mut() {
+        set_pixels_empty(font, scale);
+    }
 }
 
-fn charmap_textures(
-    textures: &mut [Texture],
+fn add_chars(
+    fonts: &mut [Vec<u8>],
     scale: usize,
     bitmap: &[u8],
     first: u8,
     color: u32,
-) -> Result<()> {
-    textures[ATTR_NONE as usize]
-        .with_lock(None, |pixels, _pitch| {
-            set_pixels(pixels, bitmap, first, ATTR_NONE, scale, color);
-        })
-        .map_err(sdl_error)?;
-    textures[ATTR_REVERSE as usize]
-        .with_lock(None, |pixels, _pitch| {
-            set_pixels(pixels, bitmap, first, ATTR_REVERSE, scale, color);
-        })
-        .map_err(sdl_error)?;
-    textures[ATTR_DIM as usize]
-        .with_lock(None, |pixels, _pitch| {
-            set_pixels(pixels, bitmap, first, ATTR_DIM, scale, color);
-        })
-        .map_err(sdl_error)?;
-    textures[(ATTR_REVERSE | ATTR_DIM) as usize]
-        .with_lock(None, |pixels, _pitch| {
-            set_pixels(
-                pixels,
-                bitmap,
-                first,
-                ATTR_REVERSE | ATTR_DIM,
-                scale,
-                color,
-            );
-        })
-        .map_err(sdl_error)?;
-    Ok(())
+) {
+    for (attrs, font) in fonts.iter_mut().enumerate() {
+        set_pixels(font, bitmap, first, attrs as u8, scale, color);
+    }
 }
 
 fn set_pixels(
@@ -327,41 +304,16 @@ fn between(color1: u32, color2: u32) -> u32 {
     (red << 16) | (green << 8) | blue
 }
 
-fn wide_charmap_textures(
-    textures: &mut [Texture],
+fn add_wide_chars(
+    fonts: &mut [Vec<u8>],
     scale: usize,
     bitmap: &[u16],
     first: u8,
     color: u32,
-) -> Result<()> {
-    textures[ATTR_NONE as usize]
-        .with_lock(None, |pixels, _pitch| {
-            set_pixels_wide(pixels, bitmap, first, ATTR_NONE, scale, color);
-        })
-        .map_err(sdl_error)?;
-    textures[ATTR_REVERSE as usize]
-        .with_lock(None, |pixels, _pitch| {
-            set_pixels_wide(pixels, bitmap, first, ATTR_REVERSE, scale, color);
-        })
-        .map_err(sdl_error)?;
-    textures[ATTR_DIM as usize]
-        .with_lock(None, |pixels, _pitch| {
-            set_pixels_wide(pixels, bitmap, first, ATTR_DIM, scale, color);
-        })
-        .map_err(sdl_error)?;
-    textures[(ATTR_REVERSE | ATTR_DIM) as usize]
-        .with_lock(None, |pixels, _pitch| {
-            set_pixels_wide(
-                pixels,
-                bitmap,
-                first,
-                ATTR_REVERSE | ATTR_DIM,
-                scale,
-                color,
-            );
-        })
-        .map_err(sdl_error)?;
-    Ok(())
+) {
+    for (attrs, font) in fonts.iter_mut().enumerate() {
+        set_pixels_wide(font, bitmap, first, attrs as u8, scale, color);
+    }
 }
 
 fn set_pixels_wide(
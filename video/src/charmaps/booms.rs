@@ -3,7 +3,7 @@ use crate::CHAR_CELL_HEIGHT;
 pub const LIL_BOOM_A1: u8 = LIL_BOOM_START;
 pub const LIL_BOOM_A2: u8 = LIL_BOOM_START + 1;
 
-pub const LIL_BOOM_START: u8 = 0x00;
+pub const LIL_BOOM_START: u8 = 0x1c;
 // comments on each line prevent rustfmt from changing this layout
 pub const LIL_BOOMS: [u8; 2 * CHAR_CELL_HEIGHT] = [
     // 1
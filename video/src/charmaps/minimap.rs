@@ -0,0 +1,97 @@
+use crate::CHAR_CELL_HEIGHT;
+
+// Every minimap character stands for a maze cell. It shows what's in the cell
+// along with the cell's top and left walls (if any).
+pub const MINIMAP_EMPTY: u8 = MINIMAP_START;
+pub const MINIMAP_PLAYER: u8 = MINIMAP_START + 4;
+pub const MINIMAP_FACTORY: u8 = MINIMAP_START + 8;
+pub const MINIMAP_RATS: u8 = MINIMAP_START + 12;
+// added to any of the above
+pub const MINIMAP_TOP_WALL: u8 = 0x01;
+pub const MINIMAP_LEFT_WALL: u8 = 0x02;
+
+pub const MINIMAP_START: u8 = 0x00;
+pub const MINIMAP: [u8; 16 * CHAR_CELL_HEIGHT] = minimap();
+
+// comments on each line prevent rustfmt from changing this layout
+const MINIMAP_CONTENTS: [u8; 4 * CHAR_CELL_HEIGHT] = [
+    // empty
+    // 01234567
+    0b_00000000, // 00
+    0b_00000000, // 01
+    0b_00000000, // 02
+    0b_00000000, // 03
+    0b_00000000, // 04
+    0b_00000000, // 05
+    0b_00000000, // 06
+    0b_00000000, // 07
+    0b_00000000, // 08
+    0b_00000000, // 09
+    0b_00000000, // 10
+    0b_00000000, // 11
+    // player
+    // 01234567
+    0b_00000000, // 00
+    0b_00000000, // 01
+    0b_00001000, // 02
+    0b_00011100, // 03
+    0b_00111110, // 04
+    0b_01111111, // 05
+    0b_00111110, // 06
+    0b_00011100, // 07
+    0b_00001000, // 08
+    0b_00000000, // 09
+    0b_00000000, // 10
+    0b_00000000, // 11
+    // factory
+    // 01234567
+    0b_00000000, // 00
+    0b_00000000, // 01
+    0b_00000000, // 02
+    0b_00111110, // 03
+    0b_00100010, // 04
+    0b_00101010, // 05
+    0b_00100010, // 06
+    0b_00111110, // 07
+    0b_00000000, // 08
+    0b_00000000, // 09
+    0b_00000000, // 10
+    0b_00000000, // 11
+    // rats
+    // 01234567
+    0b_00000000, // 00
+    0b_00000000, // 01
+    0b_00000000, // 02
+    0b_00100100, // 03
+    0b_00000000, // 04
+    0b_00010010, // 05
+    0b_00000000, // 06
+    0b_00100100, // 07
+    0b_00000000, // 08
+    0b_00000000, // 09
+    0b_00000000, // 10
+    0b_00000000, // 11
+];
+
+// every cell content combined with every combination of walls
+const fn minimap() -> [u8; 16 * CHAR_CELL_HEIGHT] {
+    let mut glyphs = [0; 16 * CHAR_CELL_HEIGHT];
+    let mut glyph = 0;
+    while glyph < 16 {
+        let content = glyph / 4;
+        let mut line = 0;
+        while line < CHAR_CELL_HEIGHT {
+            let mut bits = MINIMAP_CONTENTS[content * CHAR_CELL_HEIGHT + line];
+            if glyph as u8 & MINIMAP_TOP_WALL != 0 && line == 0 {
+                bits = 0xff;
+            }
+            if glyph as u8 & MINIMAP_LEFT_WALL != 0 {
+                bits |= 0x80;
+            }
+            glyphs[glyph * CHAR_CELL_HEIGHT + line] = bits;
+            line += 1;
+        }
+        glyph += 1;
+    }
+    glyphs
+}
@@ -6,6 +6,7 @@ mod brats;
 mod bullets;
 mod factories;
 mod maze_walls;
+mod minimap;
 mod player;
 mod rats;
 
@@ -15,6 +16,7 @@ pub use brats::*;
 pub use bullets::*;
 pub use factories::*;
 pub use maze_walls::*;
+pub use minimap::*;
 pub use player::*;
 pub use rats::*;
 
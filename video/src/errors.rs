@@ -1,3 +1,4 @@
+use crate::Pixels;
 use sdl2::{
     render::TextureValueError, video::WindowBuildError, IntegerOrSdlError,
 };
@@ -18,6 +19,18 @@ pub enum VideoError {
 
     #[error("SDL texture value error: {0}")]
     TextureValueError(#[from] TextureValueError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("PNG error: {0}")]
+    Png(#[from] png::EncodingError),
+
+    #[error("GIF error: {0}")]
+    Gif(#[from] gif::EncodingError),
+
+    #[error("GIF frame is {0}x{1}, not {2}x{3} like the rest of the GIF")]
+    GifFrameSize(Pixels, Pixels, Pixels, Pixels),
 }
 
 pub fn sdl_error(error: String) -> VideoError {
@@ -18,6 +18,7 @@ pub struct InitOptions {
     pub density: Option<usize>,
     pub factories: Option<usize>,
     pub quiet: bool,
+    pub windowed: bool,
 }
 
 impl InitOptions {
@@ -36,7 +37,7 @@ impl InitOptions {
 
     pub fn window_width(mut self, width: Option<Pixels>) -> Self {
         if let Some(width) = width {
-            if width >= 640 {
+            if width >= MIN_WINDOW_WIDTH {
                 // make sure width is a multiple of CHAR_CELL_WIDTH
                 self.window_width = Some(width - width % CHAR_CELL_WIDTH);
             }
@@ -46,7 +47,7 @@ impl InitOptions {
 
     pub fn window_height(mut self, height: Option<Pixels>) -> Self {
         if let Some(height) = height {
-            if height >= 480 {
+            if height >= MIN_WINDOW_HEIGHT {
                 // make sure height is a multiple of CHAR_CELL_HEIGHT
                 self.window_height = Some(height - height % CHAR_CELL_HEIGHT);
             }
@@ -83,6 +84,25 @@ impl InitOptions {
         self.quiet = quiet;
         self
     }
+
+    pub fn windowed(mut self, windowed: bool) -> Self {
+        self.windowed = windowed;
+        self
+    }
+}
+
+// smallest window that still fits the status lines
+const MIN_WINDOW_WIDTH: Pixels = 640;
+const MIN_WINDOW_HEIGHT: Pixels = 480;
+
+// smallest scale that makes the screen at most 80 columns wide
+pub(crate) fn fit_scale(width: u32) -> usize {
+    for scale in 1..=4 {
+        if width / scale / 8 <= 80 {
+            return scale as usize;
+        }
+    }
+    4
 }
 
 pub fn init(opts: InitOptions) -> Result<Video> {
@@ -91,31 +111,37 @@ pub fn init(opts: InitOptions) -> Result<Video> {
     let bounds = video
         .display_bounds(opts.display_index.unwrap_or(0) as i32)
         .map_err(sdl_error)?;
+    let display = bounds;
+    // windows start out at three quarters of the display unless they are
+    // given a size
+    let (width, height) = if opts.windowed {
+        (bounds.width() * 3 / 4, bounds.height() * 3 / 4)
+    } else {
+        (bounds.width(), bounds.height())
+    };
     let bounds = Rect::new(
         bounds.x(),
         bounds.y(),
-        opts.window_width.unwrap_or(bounds.width() as usize) as u32,
-        opts.window_height.unwrap_or(bounds.height() as usize) as u32,
+        opts.window_width.unwrap_or(width as usize) as u32,
+        opts.window_height.unwrap_or(height as usize) as u32,
     );
-    let scale =
-        opts.scale
-            .map(|scale| scale.clamp(1, 4))
-            .unwrap_or_else(|| {
-                for scale in 1..=4 {
-                    if bounds.width() / scale / 8 <= 80 {
-                        return scale as usize;
-                    }
-                }
-                4
-            });
+    let fixed_scale = opts.scale.map(|scale| scale.clamp(1, 4));
+    let scale = fixed_scale.unwrap_or_else(|| fit_scale(bounds.width()));
     let rows = (bounds.height() as usize / CHAR_CELL_HEIGHT / scale) as Size;
     let cols = (bounds.width() as usize / CHAR_CELL_WIDTH / scale) as Size;
-    let window = video
-        .window("", bounds.width(), bounds.height())
-        .fullscreen()
-        .position(bounds.x(), bounds.y())
-        .build()?;
-    sdl.mouse().show_cursor(false);
+    let mut window = video.window("", bounds.width(), bounds.height());
+    if opts.windowed {
+        window.resizable().position_centered();
+    } else {
+        window.fullscreen().position(bounds.x(), bounds.y());
+    }
+    let mut window = window.build()?;
+    window
+        .set_minimum_size(MIN_WINDOW_WIDTH as u32, MIN_WINDOW_HEIGHT as u32)?;
+    sdl.mouse().show_cursor(opts.windowed);
+    // controllers that are already plugged in show up as
+    // Event::ControllerDeviceAdded events
+    let controllers = sdl.game_controller().map_err(sdl_error)?;
     let canvas = window.into_canvas().build()?;
 
     sdl2::mixer::open_audio(44_100, AUDIO_S16LSB, DEFAULT_CHANNELS, 1024)
@@ -125,7 +151,10 @@ pub fn init(opts: InitOptions) -> Result<Video> {
 
     Ok(Video {
         sdl,
+        controllers,
+        display,
         bounds,
+        fixed_scale,
         scale,
         rows,
         cols,
@@ -1,14 +1,16 @@
 use buffer::ATTR_MASK;
+use init::fit_scale;
 use num::{traits::Unsigned, One, Zero};
 use sdl2::{
     pixels::Color,
     rect::Rect,
-    render::{Canvas, Texture},
-    video::Window,
-    Sdl,
+    render::Canvas,
+    video::{FullscreenType, Window, WindowPos},
+    GameControllerSubsystem, Sdl,
 };
 
-pub use sdl2::event::Event;
+pub use sdl2::controller::{Axis, Button, GameController};
+pub use sdl2::event::{Event, WindowEvent};
 pub use sdl2::keyboard::Keycode;
 
 mod buffer;
@@ -16,13 +18,19 @@ mod charmap_textures;
 mod charmaps;
 mod errors;
 mod init;
+mod software;
 mod sounds;
 
-pub use buffer::{Buffer, ATTR_COMBOS, ATTR_DIM, ATTR_NONE, ATTR_REVERSE};
+pub use buffer::{
+    Buffer, Viewport, ATTR_COMBOS, ATTR_DIM, ATTR_NONE, ATTR_REVERSE,
+};
 pub use charmaps::*;
-pub use errors::{sdl_error, Result};
+pub use errors::{sdl_error, Result, VideoError};
 pub use init::{init, InitOptions};
 pub use sdl2::pixels::PixelFormatEnum;
+pub use sdl2::render::{Texture, TextureCreator};
+pub use sdl2::video::WindowContext;
+pub use software::{GifRecorder, Image, SoftwareRenderer};
 pub use sounds::*;
 
 // use Pixels for bitmap dimensions
@@ -34,7 +42,12 @@ pub type Size = u16;
 
 pub struct Video {
     pub sdl: Sdl,
+    controllers: GameControllerSubsystem,
+    // bounds of the display the game started on
+    display: Rect,
     bounds: Rect,
+    // scale given on the command line (otherwise it depends on the width)
+    fixed_scale: Option<usize>,
     pub scale: usize,
     rows: Size,
     cols: Size,
@@ -68,6 +81,61 @@ impl Video {
         self.bounds.width() as Pixels
     }
 
+    // joystick_index comes from Event::ControllerDeviceAdded, the controller
+    // is closed when it's dropped
+    pub fn open_controller(
+        &self,
+        joystick_index: u32,
+    ) -> Result<GameController> {
+        Ok(self.controllers.open(joystick_index)?)
+    }
+
+    // the window changed size (Event::Window with WindowEvent::SizeChanged),
+    // returns true if the scale changed and the charmap textures have to be
+    // rebuilt
+    pub fn resize(&mut self, width: Pixels, height: Pixels) -> bool {
+        let scale = self.scale;
+        self.bounds = Rect::new(
+            self.bounds.x(),
+            self.bounds.y(),
+            width as u32,
+            height as u32,
+        );
+        self.scale =
+            self.fixed_scale.unwrap_or_else(|| fit_scale(width as u32));
+        self.rows = (height / CHAR_CELL_HEIGHT / self.scale) as Size;
+        self.cols = (width / CHAR_CELL_WIDTH / self.scale) as Size;
+        self.buffer = Buffer::new(self.rows, self.cols);
+        self.scale != scale
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.canvas.window().fullscreen_state() != FullscreenType::Off
+    }
+
+    // switch between fullscreen and a window three quarters the size of the
+    // display, the new size arrives as a WindowEvent::SizeChanged event
+    pub fn toggle_fullscreen(&mut self) -> Result<()> {
+        let fullscreen = !self.is_fullscreen();
+        let window = self.canvas.window_mut();
+        if fullscreen {
+            window
+                .set_fullscreen(FullscreenType::Desktop)
+                .map_err(sdl_error)?;
+        } else {
+            window
+                .set_fullscreen(FullscreenType::Off)
+                .map_err(sdl_error)?;
+            window.set_size(
+                self.display.width() * 3 / 4,
+                self.display.height() * 3 / 4,
+            )?;
+            window.set_position(WindowPos::Centered, WindowPos::Centered);
+        }
+        self.sdl.mouse().show_cursor(!fullscreen);
+        Ok(())
+    }
+
     pub fn rows(&self) -> Size {
         self.rows
     }
@@ -0,0 +1,240 @@
+use crate::{
+    buffer::{Buffer, ATTR_MASK},
+    charmap_textures::charmap_pixels,
+    Pixels, Result, VideoError, BYTES_PER_PIXEL, CHAR_CELL_HEIGHT,
+    CHAR_CELL_WIDTH,
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+// An RGB image (BYTES_PER_PIXEL bytes per pixel, row by row)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: Pixels,
+    pub height: Pixels,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder =
+            png::Encoder::new(file, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+// Draws a buffer into an image in memory the same way render_buffer draws it
+// on the screen, using the same character bitmaps as the charmap textures but
+// without SDL, a window or a GPU.
+pub struct SoftwareRenderer {
+    scale: usize,
+    fonts: Vec<Vec<u8>>,
+}
+
+impl SoftwareRenderer {
+    pub fn new(scale: usize, color: bool) -> SoftwareRenderer {
+        let scale = scale.clamp(1, 4);
+        SoftwareRenderer {
+            scale,
+            fonts: charmap_pixels(scale, color),
+        }
+    }
+
+    pub fn render(&self, buffer: &Buffer) -> Image {
+        let cell_width = CHAR_CELL_WIDTH * self.scale;
+        let cell_height = CHAR_CELL_HEIGHT * self.scale;
+        let width = buffer.cols as usize * cell_width;
+        let height = buffer.rows as usize * cell_height;
+        // bytes in a row of a character cell and a row of the image
+        let cell_pitch = cell_width * BYTES_PER_PIXEL;
+        let pitch = width * BYTES_PER_PIXEL;
+        let mut pixels = vec![0; height * pitch];
+        for row in 0..buffer.rows {
+            for col in 0..buffer.cols {
+                let ch = buffer.get_char(row, col) as usize;
+                let attr = buffer.get_attr(row, col) & ATTR_MASK;
+                let font = &self.fonts[attr as usize];
+                let mut src = ch * cell_height * cell_pitch;
+                let mut dst = row as usize * cell_height * pitch
+                    + col as usize * cell_pitch;
+                for _ in 0..cell_height {
+                    pixels[dst..dst + cell_pitch]
+                        .copy_from_slice(&font[src..src + cell_pitch]);
+                    src += cell_pitch;
+                    dst += pitch;
+                }
+            }
+        }
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+// An animated GIF which is written one frame at a time, every frame has to be
+// the same size as the first one
+pub struct GifRecorder {
+    encoder: gif::Encoder<BufWriter<File>>,
+    width: Pixels,
+    height: Pixels,
+    // time between frames in hundredths of a second
+    delay: u16,
+}
+
+impl GifRecorder {
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        width: Pixels,
+        height: Pixels,
+        frame_ms: u32,
+    ) -> Result<GifRecorder> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder =
+            gif::Encoder::new(file, width as u16, height as u16, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        Ok(GifRecorder {
+            encoder,
+            width,
+            height,
+            delay: (frame_ms / 10).max(1) as u16,
+        })
+    }
+
+    pub fn width(&self) -> Pixels {
+        self.width
+    }
+
+    pub fn height(&self) -> Pixels {
+        self.height
+    }
+
+    pub fn add_frame(&mut self, image: &Image) -> Result<()> {
+        if image.width != self.width || image.height != self.height {
+            return Err(VideoError::GifFrameSize(
+                image.width,
+                image.height,
+                self.width,
+                self.height,
+            ));
+        }
+        // the character set only uses a handful of colors so every frame
+        // gets an exact palette, anything past 256 colors (which never
+        // happens) would be drawn with the first one
+        let mut colors: HashMap<&[u8], u8> = HashMap::new();
+        let mut palette = vec![];
+        let mut indices = Vec::with_capacity(self.width * self.height);
+        for rgb in image.pixels.chunks_exact(BYTES_PER_PIXEL) {
+            let index = match colors.get(rgb) {
+                Some(index) => *index,
+                None if colors.len() < 256 => {
+                    let index = colors.len() as u8;
+                    colors.insert(rgb, index);
+                    palette.extend_from_slice(rgb);
+                    index
+                }
+                None => 0,
+            };
+            indices.push(index);
+        }
+        let mut frame = gif::Frame::from_palette_pixels(
+            self.width as u16,
+            self.height as u16,
+            indices,
+            palette,
+            None,
+        );
+        frame.delay = self.delay;
+        self.encoder.write_frame(&frame)?;
+        Ok(())
+    }
+
+    // write the end of the file
+    pub fn finish(self) -> Result<()> {
+        self.encoder.into_inner()?.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ATTR_DIM, ATTR_NONE, ATTR_REVERSE, CRT_BACKGROUND, CRT_GREEN};
+
+    fn pixel(image: &Image, x: Pixels, y: Pixels) -> [u8; 3] {
+        let offset = (y * image.width + x) * BYTES_PER_PIXEL;
+        image.pixels[offset..offset + 3].try_into().unwrap()
+    }
+
+    fn rgb(color: u32) -> [u8; 3] {
+        [(color >> 16) as u8, (color >> 8) as u8, color as u8]
+    }
+
+    #[test]
+    fn render_one_character() {
+        let renderer = SoftwareRenderer::new(1, false);
+        let mut buffer = Buffer::new(1, 1);
+        // a space is all background, unless it's reversed
+        for (attr, color) in [
+            (ATTR_NONE, CRT_BACKGROUND),
+            (ATTR_DIM, CRT_BACKGROUND),
+            (ATTR_REVERSE, CRT_GREEN),
+            (ATTR_REVERSE | ATTR_DIM, 0x407f40),
+        ] {
+            buffer.set_chattr(0, 0, b' ', attr);
+            let image = renderer.render(&buffer);
+            assert_eq!((image.width, image.height), (8, 12));
+            assert_eq!(image.pixels.len(), 8 * 12 * BYTES_PER_PIXEL);
+            assert!(image
+                .pixels
+                .chunks_exact(BYTES_PER_PIXEL)
+                .all(|pixel| pixel == rgb(color)));
+        }
+    }
+
+    #[test]
+    fn render_scaled_characters() {
+        let renderer = SoftwareRenderer::new(2, false);
+        let mut buffer = Buffer::new(2, 2);
+        buffer.set_chattr(0, 1, b' ', ATTR_REVERSE);
+        // the bar of a - is the sixth line of the character
+        buffer.set_chattr(1, 0, b'-', ATTR_NONE);
+        buffer.set_chattr(1, 1, b'-', ATTR_REVERSE);
+        let image = renderer.render(&buffer);
+        assert_eq!((image.width, image.height), (32, 48));
+        assert_eq!(pixel(&image, 0, 0), rgb(CRT_BACKGROUND));
+        assert_eq!(pixel(&image, 16, 0), rgb(CRT_GREEN));
+        assert_eq!(pixel(&image, 0, 24 + 10), rgb(CRT_GREEN));
+        assert_eq!(pixel(&image, 0, 24), rgb(CRT_BACKGROUND));
+        assert_eq!(pixel(&image, 16, 24 + 10), rgb(CRT_BACKGROUND));
+        assert_eq!(pixel(&image, 16, 24), rgb(CRT_GREEN));
+    }
+
+    #[test]
+    fn gif_frames_must_be_the_same_size() {
+        let renderer = SoftwareRenderer::new(1, false);
+        let path = std::env::temp_dir()
+            .join(format!("rats-video-{}.gif", std::process::id()));
+        let mut gif = GifRecorder::create(&path, 8, 12, 100).unwrap();
+        let result = gif.add_frame(&renderer.render(&Buffer::new(1, 1)));
+        let wrong_size = gif.add_frame(&renderer.render(&Buffer::new(1, 2)));
+        gif.finish().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+        assert!(matches!(
+            wrong_size,
+            Err(VideoError::GifFrameSize(16, 12, 8, 12))
+        ));
+    }
+}
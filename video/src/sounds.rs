@@ -1,7 +1,25 @@
 use crate::{sdl_error, Result, Video};
 use sdl2::mixer::{Channel, Chunk};
 
+// sound effects that can be queued up by code that doesn't own a Video
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    Gunshot,
+    Impact,
+    ShortExplosion,
+    LongExplosion,
+}
+
 impl Video {
+    pub fn play_sound(&self, sound: Sound) {
+        match sound {
+            Sound::Gunshot => self.play_gunshot(),
+            Sound::Impact => self.play_impact(),
+            Sound::ShortExplosion => self.play_short_explosion(),
+            Sound::LongExplosion => self.play_long_explosion(),
+        }
+    }
+
     pub fn play_gunshot(&self) {
         self.play(&self.sounds.gunshot);
     }
@@ -1,8 +1,9 @@
 use clap::Parser;
-use std::time::Instant;
+use std::{path::PathBuf, time::Instant};
 use video::{
-    init, Event, InitOptions, Keycode, PixelFormatEnum, ATTR_COMBOS, ATTR_DIM,
-    ATTR_NONE, ATTR_REVERSE, CHAR_CELL_HEIGHT, CHAR_CELL_WIDTH, FONT_SIZE,
+    init, Buffer, Event, InitOptions, Keycode, PixelFormatEnum,
+    SoftwareRenderer, ATTR_COMBOS, ATTR_DIM, ATTR_NONE, ATTR_REVERSE,
+    CHAR_CELL_HEIGHT, CHAR_CELL_WIDTH, FONT_SIZE,
 };
 
 #[derive(Parser, Debug)]
@@ -26,10 +27,18 @@ struct Options {
     /// Add some coloe
     #[clap(short = 'c', long = "color", action)]
     color: bool,
+
+    /// Save the whole font (every attribute) to a PNG file without a window
+    #[clap(long = "png")]
+    png: Option<PathBuf>,
 }
 
 fn main() {
     let opts = Options::parse();
+    if let Some(path) = &opts.png {
+        save_png(path, &opts);
+        return;
+    }
     let mut video = init(
         InitOptions::new()
             .display_index(opts.display)
@@ -121,3 +130,18 @@ fn main() {
         cols = video.cols()
     );
 }
+
+// 16 x 16 characters for each attribute combination side by side
+fn save_png(path: &PathBuf, opts: &Options) {
+    let mut buffer = Buffer::new(16, 16 * ATTR_COMBOS as u16);
+    for attr in 0..ATTR_COMBOS as u16 {
+        for ch in 0..FONT_SIZE {
+            let (row, col) = (ch / 16, attr * 16 + ch % 16);
+            buffer.set_chattr(row, col, ch as u8, attr as u8);
+        }
+    }
+    let renderer = SoftwareRenderer::new(opts.scale.unwrap_or(1), opts.color);
+    if let Err(error) = renderer.render(&buffer).save_png(path) {
+        println!("{error}");
+    }
+}